@@ -0,0 +1,147 @@
+use crate::shapes::{Shape, ShapeBody, ShapeType};
+use std::ops::{Index, IndexMut};
+
+/// Rows above the visible field where new shapes spawn.
+pub const HIDDEN_ROWS: usize = 2;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Board {
+    width: usize,
+    height: usize,
+    cells: Vec<u8>,
+}
+
+impl Board {
+    /// Creates an empty board with `height` visible rows plus the hidden spawn rows.
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![0; width * (height + HIDDEN_ROWS)],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Number of visible rows.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn hidden_rows(&self) -> usize {
+        HIDDEN_ROWS
+    }
+
+    /// All rows from the top, including the hidden ones.
+    pub fn rows(&self) -> impl Iterator<Item = &[u8]> {
+        self.cells.chunks(self.width)
+    }
+
+    pub fn visible_rows(&self) -> impl Iterator<Item = &[u8]> {
+        self.rows().skip(HIDDEN_ROWS)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.iter().all(|c| *c == 0)
+    }
+
+    pub fn fits(&self, body: &ShapeBody) -> bool {
+        body.iter()
+            .all(|p| p.x < self.width && p.y < self.height + HIDDEN_ROWS && self[p.y][p.x] == 0)
+    }
+
+    pub(crate) fn place(&mut self, shape: &Shape) {
+        let value = cell_value(shape.shape_type);
+        for p in shape.body.iter() {
+            self[p.y][p.x] = value;
+        }
+    }
+
+    /// The full rows from the top, which `remove_completed_lines` would remove.
+    pub fn completed_rows(&self) -> Vec<usize> {
+        (0..self.height + HIDDEN_ROWS)
+            .filter(|y| self[*y].iter().all(|c| *c != 0))
+            .collect()
+    }
+
+    /// Removes full rows, moving everything above them down, and returns how many were removed.
+    pub(crate) fn remove_completed_lines(&mut self) -> u32 {
+        let mut lines = 0_u32;
+        for y in 0..self.height + HIDDEN_ROWS {
+            if !self[y].iter().any(|n| n == &0) {
+                self.cells.copy_within(0..y * self.width, self.width);
+                self[0].fill(0);
+                lines += 1;
+            }
+        }
+
+        lines
+    }
+}
+
+impl Index<usize> for Board {
+    type Output = [u8];
+
+    fn index(&self, y: usize) -> &[u8] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+}
+
+impl IndexMut<usize> for Board {
+    fn index_mut(&mut self, y: usize) -> &mut [u8] {
+        &mut self.cells[y * self.width..(y + 1) * self.width]
+    }
+}
+
+/// The value a cell holds when occupied by `shape_type`, empty cells are 0.
+pub fn cell_value(shape_type: ShapeType) -> u8 {
+    match shape_type {
+        ShapeType::TForm => 1,
+        ShapeType::LForm => 2,
+        ShapeType::LMirrored => 3,
+        ShapeType::Line => 4,
+        ShapeType::SForm => 5,
+        ShapeType::SMirrored => 6,
+        ShapeType::Quadrat => 7,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::shapes::Direction;
+
+    #[test]
+    fn board_test() {
+        let mut board = Board::new(4, 6);
+        assert_eq!(board.width(), 4);
+        assert_eq!(board.height(), 6);
+        assert_eq!(board.rows().count(), 8);
+        assert_eq!(board.visible_rows().count(), 6);
+
+        let line = Shape::new(ShapeType::Line, Direction::Top, 4);
+        assert!(board.fits(&line.body));
+        assert!(line.right().filter(|s| board.fits(&s.body)).is_none());
+
+        board.place(&line);
+        assert_eq!(&board[1], &[4, 4, 4, 4]);
+        assert!(!board.fits(&line.body));
+    }
+
+    #[test]
+    fn remove_completed_lines_test() {
+        let mut board = Board::new(4, 6);
+        board[3].copy_from_slice(&[1, 0, 0, 0]);
+        board[4].copy_from_slice(&[1, 1, 1, 1]);
+        board[5].copy_from_slice(&[0, 2, 2, 0]);
+        board[6].copy_from_slice(&[3, 3, 3, 3]);
+        board[7].copy_from_slice(&[4, 4, 4, 4]);
+
+        assert_eq!(board.remove_completed_lines(), 3);
+        assert!(board[5].iter().all(|c| *c == 0));
+        assert_eq!(&board[6], &[1, 0, 0, 0]);
+        assert_eq!(&board[7], &[0, 2, 2, 0]);
+    }
+}
@@ -0,0 +1,399 @@
+use crate::glyphs::Glyphs;
+use crate::keymap::{Action, Key, Keymap};
+use crate::theme::{parse_color, ColorDepth, Theme};
+use crossterm::style::Color;
+use std::{fs, io::ErrorKind, path::Path};
+use tetris::{randomizer::FixedSequence, GravityCurve, Handling, LockReset, RandomizerKind, Rules};
+use toml::{Table, Value};
+
+/// Settings read from the config file, which the command line arguments override.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    pub rules: Rules,
+    pub handling: Handling,
+    pub ghost: bool,
+    pub theme: Theme,
+    /// Colours the terminal shows, `None` to detect them.
+    pub colors: Option<ColorDepth>,
+    /// Characters to draw with, `None` to pick them by the locale.
+    pub glyphs: Option<&'static Glyphs>,
+    pub keys: Keymap,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            rules: Rules::default(),
+            handling: Handling::default(),
+            ghost: true,
+            theme: Theme::default(),
+            colors: None,
+            glyphs: None,
+            keys: Keymap::default(),
+        }
+    }
+}
+
+/// Reads the config file at `path`, a missing file gives the defaults.
+pub fn load(path: &Path) -> Result<Config, String> {
+    match fs::read_to_string(path) {
+        Ok(text) => parse(&text).map_err(|e| format!("{}: {}", path.display(), e)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(Config::default()),
+        Err(e) => Err(format!("{}: {}", path.display(), e)),
+    }
+}
+
+/// Reads a config such as
+///
+/// ```toml
+/// [rules]
+/// gravity = "nes"
+/// width = 10
+///
+/// [handling]
+/// das = 100
+///
+/// [visuals]
+/// ghost = false
+/// theme = "mine"
+/// colors = "256"
+/// glyphs = "ascii"
+///
+/// [themes.mine]
+/// base = "light"
+/// i = "#00a0a0"
+/// ghost = 250
+///
+/// [keys]
+/// left = ["left", "a", "h"]
+/// right = ["right", "d", "l"]
+/// soft_drop = ["down", "s", "j"]
+/// rotate_left = ["up", "w", "k"]
+/// hold = "shift+c"
+/// ```
+pub fn parse(text: &str) -> Result<Config, String> {
+    let table = text.parse::<Table>().map_err(|e| {
+        let line = e
+            .span()
+            .map_or(1, |span| text[..span.start].lines().count().max(1));
+        format!("line {}: {}", line, e.message().trim_end())
+    })?;
+    let mut config = Config::default();
+    let mut theme = None;
+    let mut themes = Vec::new();
+
+    for (section, value) in &table {
+        let values = value
+            .as_table()
+            .ok_or_else(|| format!("{} is not a section", section))?;
+        if section == "themes" {
+            for (name, value) in values {
+                themes.push((name.as_str(), parse_theme(name, value)?));
+            }
+            continue;
+        }
+
+        for (key, value) in values {
+            let name = format!("{}.{}", section, key);
+            match section.as_str() {
+                "rules" => set_rule(&mut config.rules, key, value),
+                "handling" => set_handling(&mut config.handling, key, value),
+                "visuals" => match key.as_str() {
+                    "ghost" => boolean(value).map(|ghost| config.ghost = ghost),
+                    "theme" => string(value).map(|name| theme = Some(name)),
+                    "colors" => string(value).and_then(|name| {
+                        config.colors = match name {
+                            "auto" => None,
+                            name => Some(
+                                ColorDepth::from_name(name)
+                                    .ok_or_else(|| format!("unknown colors {:?}", name))?,
+                            ),
+                        };
+                        Ok(())
+                    }),
+                    "glyphs" => string(value).and_then(|name| {
+                        config.glyphs = match name {
+                            "auto" => None,
+                            name => Some(
+                                Glyphs::from_name(name)
+                                    .ok_or_else(|| format!("unknown glyphs {:?}", name))?,
+                            ),
+                        };
+                        Ok(())
+                    }),
+                    _ => Err("unknown setting".to_string()),
+                },
+                "keys" => match Action::from_name(key) {
+                    Some(action) => keys(value).map(|keys| config.keys.bind(action, keys)),
+                    None => Err("unknown action".to_string()),
+                },
+                _ => return Err(format!("unknown section [{}]", section)),
+            }
+            .map_err(|e| format!("{}: {}", name, e))?;
+        }
+    }
+
+    if let Some(name) = theme {
+        config.theme = themes
+            .into_iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, theme)| theme)
+            .or_else(|| Theme::from_name(name))
+            .ok_or_else(|| format!("visuals.theme: unknown theme {:?}", name))?;
+    }
+
+    config.keys.validate().map_err(|e| format!("keys: {}", e))?;
+    config.rules.validate()?;
+    config.handling.validate()?;
+    Ok(config)
+}
+
+fn set_rule(rules: &mut Rules, key: &str, value: &Value) -> Result<(), String> {
+    match key {
+        "randomizer" => {
+            let name = string(value)?;
+            rules.randomizer = RandomizerKind::from_name(name)
+                .ok_or_else(|| format!("unknown randomizer {:?}", name))?;
+        }
+        "sequence" => {
+            let sequence = FixedSequence::parse(string(value)?)?;
+            rules.randomizer = RandomizerKind::Fixed(sequence);
+        }
+        "width" => rules.width = integer(value)?,
+        "height" => rules.height = integer(value)?,
+        "hold" => rules.hold = boolean(value)?,
+        "preview" => rules.preview = integer(value)?,
+        "level" => rules.start_level = integer(value)?,
+        "lines_per_level" => rules.lines_per_level = integer(value)?,
+        "lock_delay" => rules.lock_delay = integer(value)?,
+        "lock_reset" => {
+            let name = string(value)?;
+            rules.lock_reset = LockReset::from_name(name)
+                .ok_or_else(|| format!("unknown lock reset {:?}", name))?;
+        }
+        "gravity" => {
+            let name = string(value)?;
+            rules.gravity = GravityCurve::from_name(name)
+                .ok_or_else(|| format!("unknown gravity {:?}", name))?;
+        }
+        "line_clear_delay" => rules.line_clear_delay = integer(value)?,
+        "entry_delay" => rules.entry_delay = integer(value)?,
+        _ => return Err("unknown setting".to_string()),
+    }
+
+    Ok(())
+}
+
+fn set_handling(handling: &mut Handling, key: &str, value: &Value) -> Result<(), String> {
+    match key {
+        "das" => handling.das = integer(value)?,
+        "arr" => handling.arr = integer(value)?,
+        "soft_drop_factor" => handling.soft_drop_factor = integer(value)?,
+        _ => return Err("unknown setting".to_string()),
+    }
+
+    Ok(())
+}
+
+/// Reads a `[themes.name]` section, the colours of a built-in `base` theme changed by
+/// the other keys.
+fn parse_theme(name: &str, value: &Value) -> Result<Theme, String> {
+    let values = value
+        .as_table()
+        .ok_or_else(|| format!("themes.{} is not a section", name))?;
+    let mut theme = match values.get("base") {
+        Some(base) => string(base).and_then(|base| {
+            Theme::from_name(base).ok_or_else(|| format!("unknown theme {:?}", base))
+        }),
+        None => Ok(Theme::default()),
+    }
+    .map_err(|e| format!("themes.{}.base: {}", name, e))?;
+
+    for (key, value) in values.iter().filter(|(key, _)| *key != "base") {
+        color(value)
+            .and_then(|color| theme.set(key, color))
+            .map_err(|e| format!("themes.{}.{}: {}", name, key, e))?;
+    }
+
+    Ok(theme)
+}
+
+/// Reads a colour name, "#rrggbb" or a number of the 256 colour palette.
+fn color(value: &Value) -> Result<Color, String> {
+    match value.as_integer() {
+        Some(_) => integer(value).map(Color::AnsiValue),
+        None => {
+            let name = string(value)?;
+            parse_color(name).ok_or_else(|| format!("unknown colour {:?}", name))
+        }
+    }
+}
+
+/// Reads a key or a list of keys.
+fn keys(value: &Value) -> Result<Vec<Key>, String> {
+    let names = match value.as_array() {
+        Some(names) => names.iter().map(string).collect::<Result<Vec<_>, _>>()?,
+        None => vec![string(value)?],
+    };
+    names
+        .into_iter()
+        .map(|name| Key::from_name(name).ok_or_else(|| format!("unknown key {:?}", name)))
+        .collect()
+}
+
+fn integer<T: TryFrom<i64>>(value: &Value) -> Result<T, String> {
+    let n = value
+        .as_integer()
+        .ok_or_else(|| format!("expected a number, found {}", value))?;
+    T::try_from(n).map_err(|_| format!("{} is out of range", n))
+}
+
+fn boolean(value: &Value) -> Result<bool, String> {
+    value
+        .as_bool()
+        .ok_or_else(|| format!("expected true or false, found {}", value))
+}
+
+fn string(value: &Value) -> Result<&str, String> {
+    value
+        .as_str()
+        .ok_or_else(|| format!("expected a string, found {}", value))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_test() {
+        assert_eq!(parse(""), Ok(Config::default()));
+
+        let config = parse(
+            "[rules]\ngravity = \"nes\"\nwidth = 12\nhold = false\n\
+             [handling]\ndas = 100\narr = 0\n\
+             [visuals]\nghost = false\n\
+             [keys]\nhard_drop = \"Enter\"\nleft = [\"left\", \"ctrl+b\"]\n",
+        )
+        .unwrap();
+        assert_eq!(config.rules.gravity, GravityCurve::Nes);
+        assert_eq!(config.rules.width, 12);
+        assert!(!config.rules.hold);
+        assert_eq!(config.handling.das, 100);
+        assert_eq!(config.handling.arr, 0);
+        assert_eq!(config.handling.soft_drop_factor, 20);
+        assert!(!config.ghost);
+        let names = |action| {
+            config
+                .keys
+                .keys(action)
+                .iter()
+                .map(|k| k.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(Action::HardDrop), vec!["ENTER"]);
+        assert_eq!(names(Action::Left), vec!["\u{2190}", "CTRL+B"]);
+    }
+
+    #[test]
+    fn doc_example_test() {
+        let config = parse(
+            "[rules]\ngravity = \"nes\"\nwidth = 10\n\
+             [handling]\ndas = 100\n\
+             [visuals]\nghost = false\ntheme = \"mine\"\ncolors = \"256\"\nglyphs = \"ascii\"\n\
+             [themes.mine]\nbase = \"light\"\ni = \"#00a0a0\"\nghost = 250\n\
+             [keys]\nleft = [\"left\", \"a\", \"h\"]\nright = [\"right\", \"d\", \"l\"]\n\
+             soft_drop = [\"down\", \"s\", \"j\"]\nrotate_left = [\"up\", \"w\", \"k\"]\n\
+             hold = \"shift+c\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.keys.keys(Action::Left).len(), 3);
+        assert_eq!(config.glyphs, Some(&crate::glyphs::ASCII));
+    }
+
+    #[test]
+    fn theme_test() {
+        let config = parse("[visuals]\ntheme = \"monochrome\"\ncolors = \"16\"").unwrap();
+        assert_eq!(config.theme, Theme::from_name("monochrome").unwrap());
+        assert_eq!(config.colors, Some(ColorDepth::Ansi16));
+
+        let config = parse(
+            "[visuals]\ntheme = \"mine\"\n\
+             [themes.mine]\nbase = \"classic\"\ni = \"#00a0a0\"\nghost = 250\n\
+             [themes.other]\no = \"white\"",
+        )
+        .unwrap();
+        let mut mine = Theme::from_name("classic").unwrap();
+        mine.set(
+            "i",
+            Color::Rgb {
+                r: 0,
+                g: 160,
+                b: 160,
+            },
+        )
+        .unwrap();
+        mine.set("ghost", Color::AnsiValue(250)).unwrap();
+        assert_eq!(config.theme, mine);
+
+        let error = |text| parse(text).unwrap_err();
+        assert_eq!(
+            error("[visuals]\ntheme = \"dark\""),
+            "visuals.theme: unknown theme \"dark\""
+        );
+        assert_eq!(
+            error("[visuals]\ncolors = \"8\""),
+            "visuals.colors: unknown colors \"8\""
+        );
+        assert_eq!(
+            error("[themes.mine]\nbase = 1"),
+            "themes.mine.base: expected a string, found 1"
+        );
+        assert_eq!(
+            error("[themes.mine]\nt = \"teal\""),
+            "themes.mine.t: unknown colour \"teal\""
+        );
+        assert_eq!(
+            error("[themes.mine]\nt = 300"),
+            "themes.mine.t: 300 is out of range"
+        );
+        assert_eq!(
+            error("[themes.mine]\nsky = \"blue\""),
+            "themes.mine.sky: unknown setting"
+        );
+    }
+
+    #[test]
+    fn error_test() {
+        let error = |text| parse(text).unwrap_err();
+        assert_eq!(
+            error("[rules]\nwidth = \"wide\""),
+            "rules.width: expected a number, found \"wide\""
+        );
+        assert_eq!(
+            error("[rules]\nwidth = -1"),
+            "rules.width: -1 is out of range"
+        );
+        assert_eq!(
+            error("[rules]\nwidth = 100"),
+            "board width 100 is not within 4..=40"
+        );
+        assert_eq!(error("[rules]\nspeed = 1"), "rules.speed: unknown setting");
+        assert_eq!(error("[sound]\non = true"), "unknown section [sound]");
+        assert_eq!(error("rules = 1"), "rules is not a section");
+        assert_eq!(
+            error("[handling]\ndas = 5000"),
+            "DAS of 5000 ms is too long"
+        );
+        assert_eq!(error("[keys]\njump = \"j\""), "keys.jump: unknown action");
+        assert_eq!(
+            error("[keys]\nhold = \"shift\""),
+            "keys.hold: unknown key \"shift\""
+        );
+        assert_eq!(
+            error("[keys]\nhold = \"x\""),
+            "keys: X is bound to both rotate_right and hold"
+        );
+        assert_eq!(error("[keys]\nhold = []"), "keys: no key is bound to hold");
+        assert!(error("[rules]\nwidth = 10\n[rules\n").starts_with("line 3: "));
+    }
+}
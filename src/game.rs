@@ -0,0 +1,905 @@
+use crate::board::Board;
+use crate::gravity::Gravity;
+use crate::randomizer::Randomizer;
+use crate::rng::Rng;
+use crate::rules::{LockReset, Rules};
+use crate::scoring::{Scoring, TSpin};
+use crate::shapes::{Direction, Shape, ShapeType};
+use std::collections::VecDeque;
+
+/// The game advances in fixed frames, `tick` runs one of them.
+pub const FRAME_RATE: u32 = 60;
+
+/// Lock delay resets a shape gets from moving or turning under `LockReset::Move`.
+const MOVE_RESET_LIMIT: u32 = 15;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Input {
+    Left,
+    Right,
+    RotateLeft,
+    RotateRight,
+    Rotate180,
+    SoftDrop,
+    HardDrop,
+    Hold,
+}
+
+/// The last way the falling shape moved, T-spins need it to be a turn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Move {
+    Spawn,
+    Shift,
+    Drop,
+    Turn,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameEvent {
+    PieceLocked,
+    LinesCleared(u32),
+    /// A T-spin and the number of lines it cleared.
+    TSpin(TSpin, u32),
+    BackToBack,
+    Combo(u32),
+    PerfectClear,
+    LevelUp(u32),
+    GameOver,
+}
+
+/// What the game is doing between shapes. Delays are counted in frames.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Phase {
+    Falling,
+    /// The completed `rows` are shown until `elapsed` reaches `duration`.
+    LineClear {
+        rows: Vec<usize>,
+        elapsed: u32,
+        duration: u32,
+    },
+    /// The next shape enters once `elapsed` reaches `duration` (ARE).
+    Entry {
+        elapsed: u32,
+        duration: u32,
+    },
+}
+
+pub struct GameState {
+    board: Board,
+    shape: Shape,
+    scoring: Scoring,
+    game_over: bool,
+    seed: u64,
+    rng: Rng,
+    rules: Rules,
+    randomizer: Box<dyn Randomizer>,
+    hold: Option<ShapeType>,
+    hold_used: bool,
+    queue: VecDeque<ShapeType>,
+    frame: u64,
+    gravity_timer: u32,
+    lock_timer: u32,
+    move_resets: u32,
+    lowest_row: usize,
+    last_move: Move,
+    phase: Phase,
+}
+
+impl GameState {
+    pub fn new(seed: u64, rules: Rules) -> Self {
+        let randomizer = rules.randomizer.build();
+        Self::with_randomizer(seed, rules, randomizer)
+    }
+
+    pub fn with_randomizer(seed: u64, rules: Rules, mut randomizer: Box<dyn Randomizer>) -> Self {
+        let mut rng = Rng::new(seed);
+        let shape = Shape::new(randomizer.next(&mut rng), Direction::Top, rules.width);
+        let lowest_row = lowest_row(&shape);
+        let queue = (0..rules.preview)
+            .map(|_| randomizer.next(&mut rng))
+            .collect();
+        Self {
+            board: Board::new(rules.width, rules.height),
+            shape,
+            scoring: Scoring::default(),
+            game_over: false,
+            seed,
+            rng,
+            rules,
+            randomizer,
+            hold: None,
+            hold_used: false,
+            queue,
+            frame: 0,
+            gravity_timer: 0,
+            lock_timer: 0,
+            move_resets: 0,
+            lowest_row,
+            last_move: Move::Spawn,
+            phase: Phase::Falling,
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn shape(&self) -> &Shape {
+        &self.shape
+    }
+
+    pub fn score(&self) -> u32 {
+        self.scoring.score()
+    }
+
+    pub fn lines(&self) -> u32 {
+        self.scoring.lines()
+    }
+
+    pub fn level(&self) -> u32 {
+        self.rules.start_level + self.scoring.lines() / self.rules.lines_per_level
+    }
+
+    pub fn is_game_over(&self) -> bool {
+        self.game_over
+    }
+
+    pub fn phase(&self) -> &Phase {
+        &self.phase
+    }
+
+    /// Number of frames played so far.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// How fast the falling shape moves down on the current level.
+    pub fn gravity(&self) -> Gravity {
+        self.rules.gravity.gravity(self.level())
+    }
+
+    pub fn hold(&self) -> Option<ShapeType> {
+        self.hold
+    }
+
+    /// Where the falling shape would land on a hard drop.
+    pub fn ghost(&self) -> Shape {
+        move_shape_down(self.shape.clone(), &self.board, None).unwrap()
+    }
+
+    /// The upcoming shapes, the next one first.
+    pub fn next_shapes(&self) -> impl Iterator<Item = ShapeType> + '_ {
+        self.queue.iter().copied()
+    }
+
+    /// Whether the falling shape may still be swapped with the held one.
+    pub fn can_hold(&self) -> bool {
+        self.rules.hold && !self.hold_used
+    }
+
+    /// The board with the falling shape drawn into it.
+    pub fn cells(&self) -> Board {
+        let mut board = self.board.clone();
+        if self.phase == Phase::Falling {
+            board.place(&self.shape);
+        }
+        board
+    }
+
+    pub fn apply(&mut self, input: Input) -> Vec<GameEvent> {
+        if self.game_over || self.phase != Phase::Falling {
+            return Vec::new();
+        }
+
+        match input {
+            Input::Hold => return self.swap_hold(),
+            Input::HardDrop => {
+                let ghost = self.ghost();
+                let rows = lowest_row(&ghost) - lowest_row(&self.shape);
+                self.scoring.hard_drop(rows as u32);
+                if rows > 0 {
+                    self.last_move = Move::Drop;
+                }
+                self.shape = ghost;
+                return self.lock();
+            }
+            Input::SoftDrop => {
+                let next_shape = move_shape_down(self.shape.clone(), &self.board, Some(1)).unwrap();
+                let rows = lowest_row(&next_shape) - lowest_row(&self.shape);
+                self.scoring.soft_drop(rows as u32);
+                self.move_to(next_shape);
+                return Vec::new();
+            }
+            _ => (),
+        }
+
+        if let Some(next_shape) = match input {
+            Input::Left => self.shape.left(),
+            Input::Right => self.shape.right(),
+            Input::RotateLeft => self.shape.turn_left(|body| self.board.fits(body)),
+            Input::RotateRight => self.shape.turn_right(|body| self.board.fits(body)),
+            Input::Rotate180 => self.shape.turn_180(|body| self.board.fits(body)),
+            Input::SoftDrop | Input::HardDrop | Input::Hold => None,
+        }
+        .filter(|s| self.board.fits(&s.body))
+        {
+            self.move_to(next_shape);
+        }
+
+        Vec::new()
+    }
+
+    /// Advances the game by one frame: the falling shape moves down as many rows as gravity
+    /// has built up, or locks once it has rested on the ground for the lock delay.
+    pub fn tick(&mut self) -> Vec<GameEvent> {
+        if self.game_over {
+            return Vec::new();
+        }
+
+        self.frame += 1;
+
+        match &mut self.phase {
+            Phase::Falling => (),
+            Phase::LineClear { elapsed, .. } | Phase::Entry { elapsed, .. } => {
+                *elapsed += 1;
+                return self.end_phase();
+            }
+        }
+
+        match self.shape.down().filter(|s| self.board.fits(&s.body)) {
+            Some(_) => {
+                let gravity = self.gravity();
+                self.gravity_timer += gravity.rows;
+                let rows = (self.gravity_timer / gravity.frames) as usize;
+                self.gravity_timer %= gravity.frames;
+                if rows > 0 {
+                    let next_shape =
+                        move_shape_down(self.shape.clone(), &self.board, Some(rows)).unwrap();
+                    self.move_to(next_shape);
+                }
+            }
+            None => {
+                self.gravity_timer = 0;
+                self.lock_timer += 1;
+                if self.lock_timer >= frames(self.rules.lock_delay) {
+                    return self.lock();
+                }
+            }
+        }
+
+        Vec::new()
+    }
+
+    fn move_to(&mut self, shape: Shape) {
+        if shape.body == self.shape.body && shape.direction() == self.shape.direction() {
+            return;
+        }
+
+        self.last_move = if shape.direction() != self.shape.direction() {
+            Move::Turn
+        } else if shape.body[0].y != self.shape.body[0].y {
+            Move::Drop
+        } else {
+            Move::Shift
+        };
+        self.shape = shape;
+        let row = lowest_row(&self.shape);
+        if row > self.lowest_row {
+            self.lowest_row = row;
+            self.lock_timer = 0;
+            self.move_resets = 0;
+            return;
+        }
+
+        match self.rules.lock_reset {
+            LockReset::Infinite => self.lock_timer = 0,
+            LockReset::Move if self.move_resets < MOVE_RESET_LIMIT => {
+                self.move_resets += 1;
+                self.lock_timer = 0;
+            }
+            LockReset::Move | LockReset::Step => (),
+        }
+    }
+
+    fn lock(&mut self) -> Vec<GameEvent> {
+        let mut events = vec![GameEvent::PieceLocked];
+        let t_spin = self.t_spin();
+        self.board.place(&self.shape);
+
+        let level = self.level();
+        let rows = self.board.completed_rows();
+        let lines = rows.len() as u32;
+        let perfect_clear = lines > 0
+            && self
+                .board
+                .rows()
+                .all(|row| row.iter().all(|c| *c != 0) || row.iter().all(|c| *c == 0));
+        let award = self.scoring.lock(lines, level, t_spin, perfect_clear);
+        if lines > 0 {
+            events.push(GameEvent::LinesCleared(lines));
+        }
+        if let Some(t_spin) = award.t_spin {
+            events.push(GameEvent::TSpin(t_spin, lines));
+        }
+        if award.back_to_back {
+            events.push(GameEvent::BackToBack);
+        }
+        if let Some(combo) = award.combo {
+            events.push(GameEvent::Combo(combo));
+        }
+        if award.perfect_clear {
+            events.push(GameEvent::PerfectClear);
+        }
+        if self.level() > level {
+            events.push(GameEvent::LevelUp(self.level()));
+        }
+
+        self.hold_used = false;
+        self.phase = match lines {
+            0 => Phase::Entry {
+                elapsed: 0,
+                duration: frames(self.rules.entry_delay),
+            },
+            _ => Phase::LineClear {
+                rows,
+                elapsed: 0,
+                duration: frames(self.rules.line_clear_delay),
+            },
+        };
+        events.extend(self.end_phase());
+        events
+    }
+
+    /// Moves on from the line clear and entry phases once their delays are over.
+    fn end_phase(&mut self) -> Vec<GameEvent> {
+        if let Phase::LineClear {
+            elapsed, duration, ..
+        } = self.phase
+        {
+            if elapsed < duration {
+                return Vec::new();
+            }
+            self.board.remove_completed_lines();
+            self.phase = Phase::Entry {
+                elapsed: 0,
+                duration: frames(self.rules.entry_delay),
+            };
+        }
+
+        match self.phase {
+            Phase::Entry { elapsed, duration } if elapsed >= duration => {
+                let shape_type = self.next_shape_type();
+                self.spawn(shape_type)
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Recognizes a T-spin by the 3-corner rule: a T which turned into place with three of
+    /// the corners around its centre taken is a full T-spin when both corners it points at
+    /// are taken, or when it got there with the last SRS kick of a quarter turn, and a mini
+    /// otherwise.
+    fn t_spin(&self) -> Option<TSpin> {
+        if self.shape.shape_type != ShapeType::TForm || self.last_move != Move::Turn {
+            return None;
+        }
+
+        // Corners outside the board count as taken.
+        let rows = self.board.height() + self.board.hidden_rows();
+        let taken = self.shape.corners().map(|(x, y)| {
+            usize::try_from(x)
+                .ok()
+                .zip(usize::try_from(y).ok())
+                .filter(|&(x, y)| x < self.board.width() && y < rows)
+                .is_none_or(|(x, y)| self.board[y][x] != 0)
+        });
+        if taken.iter().filter(|t| **t).count() < 3 {
+            return None;
+        }
+
+        // The corners are listed clockwise from the top left, like the directions.
+        let front = match self.shape.direction() {
+            Direction::Top => [0, 1],
+            Direction::Right => [1, 2],
+            Direction::Bottom => [2, 3],
+            Direction::Left => [3, 0],
+        };
+        if front.iter().all(|&i| taken[i]) || self.shape.last_kick() {
+            Some(TSpin::Full)
+        } else {
+            Some(TSpin::Mini)
+        }
+    }
+
+    fn swap_hold(&mut self) -> Vec<GameEvent> {
+        if !self.can_hold() {
+            return Vec::new();
+        }
+
+        self.hold_used = true;
+        let shape_type = match self.hold.replace(self.shape.shape_type) {
+            Some(shape_type) => shape_type,
+            None => self.next_shape_type(),
+        };
+        self.spawn(shape_type)
+    }
+
+    fn next_shape_type(&mut self) -> ShapeType {
+        self.queue.push_back(self.randomizer.next(&mut self.rng));
+        self.queue.pop_front().unwrap()
+    }
+
+    fn spawn(&mut self, shape_type: ShapeType) -> Vec<GameEvent> {
+        self.shape = Shape::new(shape_type, Direction::Top, self.board.width());
+        self.gravity_timer = 0;
+        self.lock_timer = 0;
+        self.move_resets = 0;
+        self.lowest_row = lowest_row(&self.shape);
+        self.last_move = Move::Spawn;
+        self.phase = Phase::Falling;
+        if !self.board.fits(&self.shape.body) {
+            self.game_over = true;
+            return vec![GameEvent::GameOver];
+        }
+
+        Vec::new()
+    }
+}
+
+pub(crate) fn frames(ms: u32) -> u32 {
+    ms * FRAME_RATE / 1000
+}
+
+fn lowest_row(shape: &Shape) -> usize {
+    shape.body.iter().map(|p| p.y).max().unwrap()
+}
+
+fn move_shape_down(shape: Shape, board: &Board, steps: Option<usize>) -> Option<Shape> {
+    let mut steps = steps.unwrap_or(board.height() + board.hidden_rows());
+    let mut shape = Some(shape);
+
+    while steps > 0 {
+        steps -= 1;
+        let next = shape.as_ref().and_then(|s| s.down());
+
+        if next.as_ref().filter(|s| board.fits(&s.body)).is_some() {
+            shape = next;
+        } else {
+            break;
+        }
+    }
+
+    shape
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::gravity::GravityCurve;
+    use crate::randomizer::{FixedSequence, RandomizerKind};
+
+    fn state_with(shape: Shape) -> GameState {
+        let mut state = GameState::new(
+            0,
+            Rules {
+                preview: 0,
+                line_clear_delay: 0,
+                ..Rules::default()
+            },
+        );
+        state.lowest_row = lowest_row(&shape);
+        state.shape = shape;
+        state
+    }
+
+    #[test]
+    fn hard_drop_test() {
+        let mut state = state_with(Shape::new(ShapeType::Quadrat, Direction::Top, 10));
+
+        assert_eq!(state.apply(Input::HardDrop), vec![GameEvent::PieceLocked]);
+        assert_eq!(state.board()[20][4], 7);
+        assert_eq!(state.board()[21][5], 7);
+        assert_eq!(state.shape().body.iter().map(|p| p.y).min(), Some(0));
+    }
+
+    #[test]
+    fn gravity_test() {
+        let mut state = state_with(Shape::new(ShapeType::TForm, Direction::Top, 10));
+
+        for _ in 0..59 {
+            assert!(state.tick().is_empty());
+        }
+        assert_eq!(state.shape().body[0].y, 0);
+        state.tick();
+        assert_eq!(state.shape().body[0].y, 1);
+        assert_eq!(state.frame(), 60);
+    }
+
+    #[test]
+    fn level_test() {
+        let mut state = state_with(Shape::new(ShapeType::Line, Direction::Top, 10));
+        state.rules.start_level = 3;
+        state.rules.lines_per_level = 2;
+        assert_eq!(state.level(), 3);
+        assert_eq!(state.gravity(), GravityCurve::Guideline.gravity(3));
+
+        for y in 20..22 {
+            state.board[y][..8].fill(4);
+            state.board[y][9] = 4;
+        }
+        state.apply(Input::RotateRight);
+        for _ in 0..3 {
+            state.apply(Input::Right);
+        }
+        let events = state.apply(Input::HardDrop);
+        assert_eq!(events[1], GameEvent::LinesCleared(2));
+        assert_eq!(events.last(), Some(&GameEvent::LevelUp(4)));
+        assert_eq!(state.level(), 4);
+        assert_eq!(state.gravity(), GravityCurve::Guideline.gravity(4));
+        assert_eq!(state.score(), 300 * 3 + 2 * 18);
+
+        state.rules.start_level = 40;
+        assert_eq!(state.gravity(), GravityCurve::Guideline.gravity(20));
+    }
+
+    #[test]
+    fn gravity_curve_test() {
+        let mut state = state_with(Shape::new(ShapeType::TForm, Direction::Top, 10));
+        state.rules.gravity = GravityCurve::Tgm;
+        state.rules.start_level = 8;
+        state.tick();
+        assert_eq!(state.shape().body[0].y, 3);
+        state.tick();
+        assert_eq!(state.shape().body[0].y, 6);
+
+        state.rules.start_level = 1;
+        for _ in 0..63 {
+            state.tick();
+        }
+        assert_eq!(state.shape().body[0].y, 6);
+        state.tick();
+        assert_eq!(state.shape().body[0].y, 7);
+
+        state.rules.gravity = GravityCurve::Instant;
+        state.tick();
+        assert_eq!(state.shape().body, state.ghost().body);
+        assert_eq!(state.shape().body[0].y, 20);
+    }
+
+    fn frames_until_lock(state: &mut GameState, moves: usize) -> u32 {
+        state.shape = state.ghost();
+        state.lowest_row = lowest_row(&state.shape);
+
+        // Slides the grounded shape back and forth every 10 frames.
+        let mut moved = 0;
+        for frame in 1.. {
+            if state.tick().contains(&GameEvent::PieceLocked) {
+                return frame;
+            }
+            if frame % 10 == 0 && moved < moves {
+                state.apply(if moved % 2 == 0 {
+                    Input::Left
+                } else {
+                    Input::Right
+                });
+                moved += 1;
+            }
+        }
+        unreachable!()
+    }
+
+    #[test]
+    fn lock_delay_test() {
+        let mut state = state_with(Shape::new(ShapeType::Quadrat, Direction::Top, 10));
+        assert_eq!(frames_until_lock(&mut state, 0), 30);
+        assert_eq!(state.board()[21][4], 7);
+
+        let mut state = state_with(Shape::new(ShapeType::Quadrat, Direction::Top, 10));
+        state.rules.lock_reset = LockReset::Step;
+        assert_eq!(frames_until_lock(&mut state, 20), 30);
+
+        let mut state = state_with(Shape::new(ShapeType::Quadrat, Direction::Top, 10));
+        state.rules.lock_reset = LockReset::Infinite;
+        assert_eq!(frames_until_lock(&mut state, 20), 230);
+
+        let mut state = state_with(Shape::new(ShapeType::Quadrat, Direction::Top, 10));
+        state.rules.lock_reset = LockReset::Move;
+        assert_eq!(frames_until_lock(&mut state, 20), 180);
+
+        let mut state = state_with(Shape::new(ShapeType::Quadrat, Direction::Top, 10));
+        state.rules.lock_delay = 0;
+        assert_eq!(frames_until_lock(&mut state, 0), 1);
+    }
+
+    #[test]
+    fn ghost_test() {
+        let mut state = state_with(Shape::new(ShapeType::TForm, Direction::Top, 10));
+        state.board[15][4] = 4;
+
+        let ghost = state.ghost();
+        assert_eq!(ghost.body[0].y, 13);
+        assert_eq!(ghost.body[2].y, 14);
+        assert_eq!(state.shape().body[0].y, 0);
+
+        state.apply(Input::Right);
+        state.apply(Input::Right);
+        assert_eq!(state.ghost().body[0].y, 20);
+    }
+
+    #[test]
+    fn lines_cleared_test() {
+        let mut state = state_with(Shape::new(ShapeType::Quadrat, Direction::Top, 10));
+        for x in 0..10 {
+            if x != 4 && x != 5 {
+                state.board[20][x] = 4;
+                state.board[21][x] = 4;
+            }
+        }
+        state.board[19][0] = 4;
+
+        assert_eq!(
+            state.apply(Input::HardDrop),
+            vec![GameEvent::PieceLocked, GameEvent::LinesCleared(2)]
+        );
+        assert_eq!(state.score(), 300 + 2 * 20);
+        assert_eq!(state.lines(), 2);
+        assert_eq!(state.board()[21][0], 4);
+        assert!(state.board()[20].iter().all(|c| *c == 0));
+    }
+
+    #[test]
+    fn phase_test() {
+        let mut state = state_with(Shape::new(ShapeType::Quadrat, Direction::Top, 10));
+        state.rules.line_clear_delay = 100;
+        state.rules.entry_delay = 50;
+        state.board[21].fill(4);
+        state.board[21][4..6].fill(0);
+
+        state.apply(Input::HardDrop);
+        assert_eq!(
+            state.phase(),
+            &Phase::LineClear {
+                rows: vec![21],
+                elapsed: 0,
+                duration: 6
+            }
+        );
+        assert_eq!(state.cells(), state.board().clone());
+        assert!(state.apply(Input::Left).is_empty());
+        for _ in 0..5 {
+            state.tick();
+        }
+        assert_eq!(state.board()[21][0], 4);
+
+        state.tick();
+        assert_eq!(state.board()[21][0], 0);
+        assert_eq!(state.board()[21][4], 7);
+        assert_eq!(
+            state.phase(),
+            &Phase::Entry {
+                elapsed: 0,
+                duration: 3
+            }
+        );
+
+        for _ in 0..3 {
+            state.tick();
+        }
+        assert_eq!(state.phase(), &Phase::Falling);
+        assert_eq!(state.frame(), 9);
+        assert_ne!(state.cells(), state.board().clone());
+    }
+
+    #[test]
+    fn t_spin_test() {
+        let mut shape = Shape::new(ShapeType::TForm, Direction::Right, 10);
+        for _ in 0..19 {
+            shape = shape.down().unwrap();
+        }
+
+        // A T-spin double slot with an overhang over its left corner.
+        let mut state = state_with(shape.clone());
+        state.board[19][3] = 4;
+        state.board[20].fill(4);
+        state.board[20][3..6].fill(0);
+        state.board[21].fill(4);
+        state.board[21][4] = 0;
+        state.apply(Input::RotateRight);
+        let events = state.apply(Input::HardDrop);
+        assert_eq!(events[1], GameEvent::LinesCleared(2));
+        assert_eq!(events[2], GameEvent::TSpin(TSpin::Full, 2));
+        assert_eq!(state.score(), 1200);
+
+        // Shifting after the turn is no T-spin.
+        let mut state = state_with(shape.clone());
+        state.board[19][3] = 4;
+        state.board[19][5] = 4;
+        state.board[21][3] = 4;
+        state.apply(Input::RotateRight);
+        state.apply(Input::Right);
+        state.apply(Input::Left);
+        assert_eq!(state.apply(Input::HardDrop).len(), 1);
+
+        // Without the overhang only two corners are taken.
+        let mut state = state_with(shape);
+        state.board[21][3] = 4;
+        state.board[21][5] = 4;
+        state.apply(Input::RotateRight);
+        assert_eq!(state.apply(Input::HardDrop).len(), 1);
+
+        // Pointing up at a single taken corner, on the floor which takes the other two.
+        let mut shape = Shape::new(ShapeType::TForm, Direction::Top, 10);
+        while let Some(next) = shape.left() {
+            shape = next;
+        }
+        let mut state = state_with(shape);
+        state.shape = state.ghost();
+        state.board[20][0] = 4;
+        state.last_move = Move::Turn;
+        let events = state.apply(Input::HardDrop);
+        assert_eq!(events[1], GameEvent::TSpin(TSpin::Mini, 0));
+        assert_eq!(state.score(), 100);
+
+        // Turning from pointing down with the fourth kick, two rows straight down, leaves
+        // one front corner free and stays a mini.
+        let mut shape = Shape::new(ShapeType::TForm, Direction::Bottom, 10);
+        for _ in 0..17 {
+            shape = shape.down().unwrap();
+        }
+        let mut state = state_with(shape.clone());
+        for (x, y) in [(4, 17), (5, 19), (3, 19), (5, 21)] {
+            state.board[y][x] = 4;
+        }
+        state.apply(Input::RotateRight);
+        assert_eq!(state.shape.kick(), (0, 2));
+        let events = state.apply(Input::HardDrop);
+        assert_eq!(events[1], GameEvent::TSpin(TSpin::Mini, 0));
+
+        // The fifth kick, one column over and two rows down, makes it a full T-spin.
+        let mut state = state_with(shape);
+        for (x, y) in [(4, 17), (5, 17), (4, 21), (6, 19), (6, 21)] {
+            state.board[y][x] = 4;
+        }
+        state.apply(Input::RotateRight);
+        assert_eq!(state.shape.kick(), (1, 2));
+        let events = state.apply(Input::HardDrop);
+        assert_eq!(events[1], GameEvent::TSpin(TSpin::Full, 0));
+
+        // A half turn kicked one column over and two rows up is no SRS kick and stays a mini.
+        let mut shape = Shape::new(ShapeType::TForm, Direction::Right, 10);
+        for _ in 0..10 {
+            shape = shape.down().unwrap();
+        }
+        let mut state = state_with(shape);
+        for (x, y) in [(3, 11), (5, 12), (4, 8), (6, 8), (6, 10)] {
+            state.board[y][x] = 4;
+        }
+        state.apply(Input::Rotate180);
+        assert_eq!(state.shape.kick(), (1, -2));
+        assert_eq!(state.t_spin(), Some(TSpin::Mini));
+    }
+
+    #[test]
+    fn perfect_clear_test() {
+        let mut state = state_with(Shape::new(ShapeType::Line, Direction::Top, 10));
+        state.board[21][..6].fill(4);
+
+        for _ in 0..3 {
+            assert_eq!(state.apply(Input::Right), vec![]);
+        }
+        assert_eq!(
+            state.apply(Input::HardDrop),
+            vec![
+                GameEvent::PieceLocked,
+                GameEvent::LinesCleared(1),
+                GameEvent::PerfectClear
+            ]
+        );
+        assert!(state.board().is_empty());
+        assert_eq!(state.score(), 100 + 800 + 2 * 20);
+
+        state.apply(Input::SoftDrop);
+        assert_eq!(state.score(), 100 + 800 + 2 * 20 + 1);
+    }
+
+    #[test]
+    fn seed_test() {
+        let mut a = GameState::new(7, Rules::default());
+        let mut b = GameState::new(7, Rules::default());
+        assert_eq!(a.seed(), 7);
+
+        for _ in 0..50 {
+            assert_eq!(a.shape().body, b.shape().body);
+            assert_eq!(a.apply(Input::HardDrop), b.apply(Input::HardDrop));
+        }
+        assert_eq!(a.board(), b.board());
+    }
+
+    #[test]
+    fn hold_test() {
+        let mut state = state_with(Shape::new(ShapeType::TForm, Direction::Top, 10));
+        state.randomizer = RandomizerKind::Fixed(vec![ShapeType::Line]).build();
+
+        assert!(state.can_hold());
+        state.apply(Input::Left);
+        state.apply(Input::Hold);
+        assert_eq!(state.hold(), Some(ShapeType::TForm));
+        assert_eq!(state.shape().shape_type, ShapeType::Line);
+        assert_eq!(state.shape().body[0].x, 3);
+        assert!(!state.can_hold());
+
+        state.apply(Input::Hold);
+        assert_eq!(state.hold(), Some(ShapeType::TForm));
+        assert_eq!(state.shape().shape_type, ShapeType::Line);
+
+        state.apply(Input::HardDrop);
+        assert!(state.can_hold());
+        state.apply(Input::Hold);
+        assert_eq!(state.hold(), Some(ShapeType::Line));
+        assert_eq!(state.shape().shape_type, ShapeType::TForm);
+
+        let mut state = state_with(Shape::new(ShapeType::TForm, Direction::Top, 10));
+        state.rules.hold = false;
+        assert!(!state.can_hold());
+        state.apply(Input::Hold);
+        assert_eq!(state.hold(), None);
+        assert_eq!(state.shape().shape_type, ShapeType::TForm);
+    }
+
+    #[test]
+    fn preview_test() {
+        let sequence = FixedSequence::parse("IOTSZJL").unwrap();
+        let rules = Rules {
+            randomizer: RandomizerKind::Fixed(sequence),
+            preview: 3,
+            ..Rules::default()
+        };
+        let mut state = GameState::new(0, rules);
+
+        assert_eq!(state.shape().shape_type, ShapeType::Line);
+        assert_eq!(
+            state.next_shapes().collect::<Vec<_>>(),
+            vec![ShapeType::Quadrat, ShapeType::TForm, ShapeType::SForm]
+        );
+
+        state.apply(Input::HardDrop);
+        assert_eq!(state.shape().shape_type, ShapeType::Quadrat);
+        assert_eq!(
+            state.next_shapes().collect::<Vec<_>>(),
+            vec![ShapeType::TForm, ShapeType::SForm, ShapeType::SMirrored]
+        );
+
+        state.apply(Input::Hold);
+        assert_eq!(state.shape().shape_type, ShapeType::TForm);
+        assert_eq!(
+            state.next_shapes().collect::<Vec<_>>(),
+            vec![ShapeType::SForm, ShapeType::SMirrored, ShapeType::LMirrored]
+        );
+    }
+
+    #[test]
+    fn game_over_test() {
+        let mut state = state_with(Shape::new(ShapeType::Quadrat, Direction::Top, 10));
+        for y in 2..22 {
+            state.board[y][..9].fill(4);
+        }
+
+        for _ in 1..30 {
+            assert!(state.tick().is_empty());
+        }
+        let events = state.tick();
+        assert_eq!(events.first(), Some(&GameEvent::PieceLocked));
+        assert_eq!(events.last(), Some(&GameEvent::GameOver));
+        assert!(state.is_game_over());
+        assert!(state.apply(Input::Left).is_empty());
+        assert!(state.tick().is_empty());
+    }
+}
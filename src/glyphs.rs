@@ -0,0 +1,129 @@
+use std::env;
+
+/// The characters the game is drawn with.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Glyphs {
+    /// A cell holding a shape, the ghost piece and an empty cell, all as wide as each other.
+    pub block: &'static str,
+    pub ghost: &'static str,
+    pub empty: &'static str,
+    /// Printed between neighbouring cells.
+    pub gap: &'static str,
+    pub horizontal: char,
+    pub vertical: char,
+    /// The top left corner of a box followed by the others clockwise.
+    pub corners: [char; 4],
+    /// The arrow keys left, right, up and down.
+    pub arrows: [&'static str; 4],
+    pub degree: &'static str,
+}
+
+pub const UNICODE: Glyphs = Glyphs {
+    block: "\u{2587}",
+    ghost: "\u{25A1}",
+    empty: " ",
+    gap: " ",
+    horizontal: '\u{2500}',
+    vertical: '\u{2502}',
+    corners: ['\u{250C}', '\u{2510}', '\u{2518}', '\u{2514}'],
+    arrows: ["\u{2190}", "\u{2192}", "\u{2191}", "\u{2193}"],
+    degree: "°",
+};
+
+pub const ASCII: Glyphs = Glyphs {
+    block: "[]",
+    ghost: "::",
+    empty: "  ",
+    gap: "",
+    horizontal: '-',
+    vertical: '|',
+    corners: ['+', '+', '+', '+'],
+    arrows: ["LEFT", "RIGHT", "UP", "DOWN"],
+    degree: " degrees",
+};
+
+impl Glyphs {
+    pub fn from_name(name: &str) -> Option<&'static Glyphs> {
+        match name {
+            "unicode" => Some(&UNICODE),
+            "ascii" => Some(&ASCII),
+            _ => None,
+        }
+    }
+
+    /// Picks Unicode when the locale says the terminal speaks UTF-8.
+    pub fn detect() -> &'static Glyphs {
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .into_iter()
+            .filter_map(|name| env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .unwrap_or_default();
+        match cfg!(windows) || is_utf8(&locale) {
+            true => &UNICODE,
+            false => &ASCII,
+        }
+    }
+
+    /// Columns taken by a row of `cells` cells with gaps between them.
+    pub fn row_width(&self, cells: usize) -> usize {
+        let width = |s: &str| s.chars().count();
+        cells * width(self.block) + cells.saturating_sub(1) * width(self.gap)
+    }
+
+    /// The top of a box `width` columns wide inside, starting with `title`.
+    pub fn top(&self, title: &str, width: usize) -> String {
+        let fill = width.saturating_sub(title.chars().count());
+        let [left, right, ..] = self.corners;
+        format!(
+            "{}{}{}{}",
+            left,
+            title,
+            self.horizontal.to_string().repeat(fill),
+            right
+        )
+    }
+
+    pub fn bottom(&self, width: usize) -> String {
+        let [.., right, left] = self.corners;
+        format!(
+            "{}{}{}",
+            left,
+            self.horizontal.to_string().repeat(width),
+            right
+        )
+    }
+}
+
+/// Whether a locale such as "en_US.UTF-8" uses UTF-8.
+fn is_utf8(locale: &str) -> bool {
+    let locale = locale.to_ascii_lowercase();
+    locale.contains("utf-8") || locale.contains("utf8")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn is_utf8_test() {
+        assert!(is_utf8("en_US.UTF-8"));
+        assert!(is_utf8("de_DE.utf8"));
+        assert!(is_utf8("C.UTF-8"));
+        assert!(!is_utf8("C"));
+        assert!(!is_utf8("POSIX"));
+        assert!(!is_utf8("en_US.ISO-8859-1"));
+        assert!(!is_utf8(""));
+    }
+
+    #[test]
+    fn border_test() {
+        assert_eq!(UNICODE.row_width(10), 19);
+        assert_eq!(ASCII.row_width(10), 20);
+        assert_eq!(
+            UNICODE.top(" Hold ", 9),
+            "\u{250C} Hold \u{2500}\u{2500}\u{2500}\u{2510}"
+        );
+        assert_eq!(ASCII.top(" Hold ", 10), "+ Hold ----+");
+        assert_eq!(ASCII.bottom(3), "+---+");
+    }
+}
@@ -0,0 +1,194 @@
+/// Falling speed: `rows` rows every `frames` frames. Speeds above one row per frame move
+/// the shape several rows at once, 20 rows per frame drop it instantly (20G).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Gravity {
+    pub rows: u32,
+    pub frames: u32,
+}
+
+impl Gravity {
+    pub const TWENTY_G: Gravity = Gravity {
+        rows: 20,
+        frames: 1,
+    };
+
+    pub fn frames_per_row(frames: u32) -> Self {
+        Self { rows: 1, frames }
+    }
+}
+
+/// NES frames per row for levels 0 to 29, level 29 and up all drop a row every frame.
+const NES_FRAMES: [u32; 30] = [
+    48, 43, 38, 33, 28, 23, 18, 13, 8, 6, 5, 5, 5, 4, 4, 4, 3, 3, 3, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2,
+    1,
+];
+
+/// TGM internal gravity in 1/256 rows per frame, starting from the given internal level.
+const TGM_GRAVITY: [(u32, u32); 30] = [
+    (0, 4),
+    (30, 6),
+    (35, 8),
+    (40, 10),
+    (50, 12),
+    (60, 16),
+    (70, 32),
+    (80, 48),
+    (90, 64),
+    (100, 80),
+    (120, 96),
+    (140, 112),
+    (160, 128),
+    (170, 144),
+    (200, 4),
+    (220, 32),
+    (230, 64),
+    (233, 96),
+    (236, 128),
+    (239, 160),
+    (243, 192),
+    (247, 224),
+    (251, 256),
+    (300, 512),
+    (330, 768),
+    (360, 1024),
+    (400, 1280),
+    (420, 1024),
+    (450, 768),
+    (500, 5120),
+];
+
+/// TGM levels covered by each of our levels.
+const TGM_LEVELS_PER_LEVEL: u32 = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GravityCurve {
+    /// `(0.8 - (level - 1) * 0.007) ^ (level - 1)` seconds per row.
+    Guideline,
+    Nes,
+    Tgm,
+    /// 20G from the first level on.
+    Instant,
+}
+
+impl GravityCurve {
+    pub fn from_name(name: &str) -> Option<GravityCurve> {
+        match name {
+            "guideline" => Some(GravityCurve::Guideline),
+            "nes" => Some(GravityCurve::Nes),
+            "tgm" => Some(GravityCurve::Tgm),
+            "20g" => Some(GravityCurve::Instant),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GravityCurve::Guideline => "guideline",
+            GravityCurve::Nes => "nes",
+            GravityCurve::Tgm => "tgm",
+            GravityCurve::Instant => "20g",
+        }
+    }
+
+    /// The gravity at `level`, counting levels from 1.
+    pub fn gravity(&self, level: u32) -> Gravity {
+        let level = level.max(1);
+        match self {
+            GravityCurve::Guideline => {
+                let n = level.min(20) - 1;
+                let seconds = (0.8 - n as f64 * 0.007).powi(n as i32);
+                Gravity {
+                    rows: 1000,
+                    frames: ((seconds * 60_000.0).round() as u32).max(1),
+                }
+            }
+            GravityCurve::Nes => {
+                Gravity::frames_per_row(NES_FRAMES[(level as usize - 1).min(NES_FRAMES.len() - 1)])
+            }
+            GravityCurve::Tgm => {
+                let tgm_level = (level - 1) * TGM_LEVELS_PER_LEVEL;
+                let (_, rows) = TGM_GRAVITY
+                    .iter()
+                    .rev()
+                    .find(|(from, _)| *from <= tgm_level)
+                    .unwrap();
+                Gravity {
+                    rows: *rows,
+                    frames: 256,
+                }
+            }
+            GravityCurve::Instant => Gravity::TWENTY_G,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn guideline_test() {
+        let curve = GravityCurve::Guideline;
+        assert_eq!(
+            curve.gravity(1),
+            Gravity {
+                rows: 1000,
+                frames: 60_000
+            }
+        );
+        assert_eq!(curve.gravity(2).frames, 47_580);
+        assert_eq!(curve.gravity(10).frames, 3_849);
+        assert_eq!(curve.gravity(15).frames, 424);
+        assert_eq!(curve.gravity(20), curve.gravity(25));
+    }
+
+    #[test]
+    fn nes_test() {
+        let curve = GravityCurve::Nes;
+        assert_eq!(curve.gravity(1), Gravity::frames_per_row(48));
+        assert_eq!(curve.gravity(10), Gravity::frames_per_row(6));
+        assert_eq!(curve.gravity(30), Gravity::frames_per_row(1));
+        assert_eq!(curve.gravity(99), Gravity::frames_per_row(1));
+    }
+
+    #[test]
+    fn tgm_test() {
+        let curve = GravityCurve::Tgm;
+        assert_eq!(
+            curve.gravity(1),
+            Gravity {
+                rows: 4,
+                frames: 256
+            }
+        );
+        assert_eq!(
+            curve.gravity(2),
+            Gravity {
+                rows: 12,
+                frames: 256
+            }
+        );
+        assert_eq!(
+            curve.gravity(5),
+            Gravity {
+                rows: 4,
+                frames: 256
+            }
+        );
+        assert_eq!(
+            curve.gravity(8),
+            Gravity {
+                rows: 768,
+                frames: 256
+            }
+        );
+        assert_eq!(
+            curve.gravity(11),
+            Gravity {
+                rows: 5120,
+                frames: 256
+            }
+        );
+        assert_eq!(GravityCurve::Instant.gravity(1), Gravity::TWENTY_G);
+    }
+}
@@ -0,0 +1,247 @@
+use crate::game::{frames, GameEvent, GameState, Input};
+
+/// How held keys repeat, the player's side of the controls next to the `Rules`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Handling {
+    /// Milliseconds a sideways key is held before it starts repeating.
+    pub das: u32,
+    /// Milliseconds between repeated moves, 0 moves straight to the wall.
+    pub arr: u32,
+    /// How many times faster than gravity a held soft drop moves the shape.
+    pub soft_drop_factor: u32,
+}
+
+impl Default for Handling {
+    fn default() -> Self {
+        Self {
+            das: 167,
+            arr: 33,
+            soft_drop_factor: 20,
+        }
+    }
+}
+
+impl Handling {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.das > 1000 {
+            return Err(format!("DAS of {} ms is too long", self.das));
+        }
+        if self.arr > 1000 {
+            return Err(format!("ARR of {} ms is too long", self.arr));
+        }
+        if !(1..=100).contains(&self.soft_drop_factor) {
+            return Err(format!(
+                "soft drop factor {} is not within 1..=100",
+                self.soft_drop_factor
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// A key going down or up, what replays record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputEvent {
+    Press(Input),
+    Release(Input),
+}
+
+/// Turns key presses and releases into game inputs, repeating held moves every frame.
+#[derive(Debug, Clone)]
+pub struct Controller {
+    handling: Handling,
+    /// Held sideways keys, the most recent one last.
+    held: Vec<Input>,
+    /// Frames the current sideways key has been held.
+    shift_timer: u32,
+    soft_drop: bool,
+    soft_drop_timer: u32,
+}
+
+impl Controller {
+    pub fn new(handling: Handling) -> Self {
+        Self {
+            handling,
+            held: Vec::new(),
+            shift_timer: 0,
+            soft_drop: false,
+            soft_drop_timer: 0,
+        }
+    }
+
+    pub fn handling(&self) -> &Handling {
+        &self.handling
+    }
+
+    /// Applies `input` right away; moves and soft drop then repeat until `release`.
+    pub fn press(&mut self, state: &mut GameState, input: Input) -> Vec<GameEvent> {
+        match input {
+            Input::Left | Input::Right => {
+                self.held.retain(|i| *i != input);
+                self.held.push(input);
+                self.shift_timer = 0;
+            }
+            Input::SoftDrop => {
+                self.soft_drop = true;
+                self.soft_drop_timer = 0;
+            }
+            _ => (),
+        }
+
+        state.apply(input)
+    }
+
+    pub fn release(&mut self, input: Input) {
+        match input {
+            Input::Left | Input::Right => {
+                if self.held.last() == Some(&input) {
+                    self.shift_timer = 0;
+                }
+                self.held.retain(|i| *i != input);
+            }
+            Input::SoftDrop => self.soft_drop = false,
+            _ => (),
+        }
+    }
+
+    pub fn handle(&mut self, state: &mut GameState, event: InputEvent) -> Vec<GameEvent> {
+        match event {
+            InputEvent::Press(input) => self.press(state, input),
+            InputEvent::Release(input) => {
+                self.release(input);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Repeats the held keys for one frame, then advances the game by that frame.
+    pub fn tick(&mut self, state: &mut GameState) -> Vec<GameEvent> {
+        let mut events = Vec::new();
+
+        if let Some(&input) = self.held.last() {
+            self.shift_timer += 1;
+            let das = frames(self.handling.das);
+            if self.shift_timer >= das {
+                let arr = frames(self.handling.arr);
+                let moves = match arr {
+                    0 => state.board().width(),
+                    _ if (self.shift_timer - das).is_multiple_of(arr) => 1,
+                    _ => 0,
+                };
+                for _ in 0..moves {
+                    events.extend(state.apply(input));
+                }
+            }
+        }
+
+        if self.soft_drop {
+            let gravity = state.gravity();
+            self.soft_drop_timer += gravity.rows * self.handling.soft_drop_factor;
+            let rows = self.soft_drop_timer / gravity.frames;
+            self.soft_drop_timer %= gravity.frames;
+            for _ in 0..rows.min(state.board().height() as u32) {
+                events.extend(state.apply(Input::SoftDrop));
+            }
+        }
+
+        events.extend(state.tick());
+        events
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::randomizer::FixedSequence;
+    use crate::shapes::ShapeType;
+    use crate::Rules;
+
+    fn state() -> GameState {
+        let rules = Rules {
+            preview: 0,
+            lock_delay: 10_000,
+            ..Rules::default()
+        };
+        let randomizer = FixedSequence::new(vec![ShapeType::TForm]);
+        GameState::with_randomizer(0, rules, Box::new(randomizer))
+    }
+
+    fn column(state: &GameState) -> usize {
+        state.shape().body.iter().map(|p| p.x).min().unwrap()
+    }
+
+    #[test]
+    fn auto_shift_test() {
+        let mut state = state();
+        let mut controller = Controller::new(Handling {
+            das: 100,
+            arr: 50,
+            soft_drop_factor: 20,
+        });
+
+        // DAS is 6 frames, ARR 3.
+        controller.press(&mut state, Input::Right);
+        assert_eq!(column(&state), 4);
+        for _ in 0..5 {
+            controller.tick(&mut state);
+        }
+        assert_eq!(column(&state), 4);
+        controller.tick(&mut state);
+        assert_eq!(column(&state), 5);
+        for _ in 0..3 {
+            controller.tick(&mut state);
+        }
+        assert_eq!(column(&state), 6);
+
+        // The later key wins, and releasing it hands back to the earlier one.
+        controller.press(&mut state, Input::Left);
+        assert_eq!(column(&state), 5);
+        controller.release(Input::Left);
+        for _ in 0..6 {
+            controller.tick(&mut state);
+        }
+        assert_eq!(column(&state), 6);
+
+        controller.release(Input::Right);
+        for _ in 0..20 {
+            controller.tick(&mut state);
+        }
+        assert_eq!(column(&state), 6);
+    }
+
+    #[test]
+    fn instant_shift_test() {
+        let mut state = state();
+        let mut controller = Controller::new(Handling {
+            das: 0,
+            arr: 0,
+            soft_drop_factor: 20,
+        });
+
+        controller.press(&mut state, Input::Left);
+        controller.tick(&mut state);
+        assert_eq!(column(&state), 0);
+    }
+
+    #[test]
+    fn soft_drop_test() {
+        let mut state = state();
+        let mut controller = Controller::new(Handling::default());
+
+        // Level 1 gravity is a row a second, 20 times faster is one every 3 frames.
+        controller.press(&mut state, Input::SoftDrop);
+        assert_eq!(state.shape().body[0].y, 1);
+        for _ in 0..9 {
+            controller.tick(&mut state);
+        }
+        assert_eq!(state.shape().body[0].y, 4);
+        assert_eq!(state.score(), 4);
+
+        controller.release(Input::SoftDrop);
+        for _ in 0..9 {
+            controller.tick(&mut state);
+        }
+        assert_eq!(state.shape().body[0].y, 4);
+    }
+}
@@ -0,0 +1,191 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+const MAGIC: &str = "tetris-highscores";
+const VERSION: u32 = 1;
+
+/// Scores kept for each game mode.
+pub const TABLE_SIZE: usize = 10;
+
+/// Longest name a score can be entered with.
+pub const MAX_NAME_LENGTH: usize = 12;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub name: String,
+    pub score: u32,
+    pub lines: u32,
+    pub level: u32,
+}
+
+/// The best scores of each game mode, highest first.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HighScores {
+    tables: BTreeMap<String, Vec<Entry>>,
+}
+
+impl HighScores {
+    pub fn table(&self, mode: &str) -> &[Entry] {
+        self.tables.get(mode).map_or(&[], |t| t.as_slice())
+    }
+
+    /// Whether `score` would make it into the table of `mode`.
+    pub fn qualifies(&self, mode: &str, score: u32) -> bool {
+        let table = self.table(mode);
+        score > 0 && (table.len() < TABLE_SIZE || table.iter().any(|e| e.score < score))
+    }
+
+    /// Adds `entry` to the table of `mode`, returning its place counting from 1, or `None`
+    /// when it did not qualify. Later entries rank below earlier ones with the same score.
+    pub fn insert(&mut self, mode: &str, mut entry: Entry) -> Option<usize> {
+        if !self.qualifies(mode, entry.score) {
+            return None;
+        }
+
+        entry.name = clean_name(&entry.name);
+        let table = self.tables.entry(mode.to_string()).or_default();
+        let place = table.iter().take_while(|e| e.score >= entry.score).count();
+        table.insert(place, entry);
+        table.truncate(TABLE_SIZE);
+        Some(place + 1)
+    }
+
+    /// Reads the tables in the format `Display` writes.
+    pub fn parse(text: &str) -> Result<HighScores, String> {
+        let mut lines = text.lines();
+        let version = lines
+            .next()
+            .and_then(|line| line.strip_prefix(MAGIC))
+            .ok_or("not a high score file")?;
+        if version.trim() != VERSION.to_string() {
+            return Err(format!("unsupported high score version {}", version.trim()));
+        }
+
+        let mut high_scores = HighScores::default();
+        let mut mode = None;
+        for line in lines.filter(|line| !line.trim().is_empty()) {
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                mode = Some(name.to_string());
+                continue;
+            }
+
+            let mode = mode
+                .as_ref()
+                .ok_or_else(|| format!("score outside of a mode: {}", line))?;
+            let mut fields = line.splitn(4, ' ');
+            let mut number = || {
+                fields
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .ok_or_else(|| format!("invalid score: {}", line))
+            };
+            let entry = Entry {
+                score: number()?,
+                lines: number()?,
+                level: number()?,
+                name: fields.next().unwrap_or_default().to_string(),
+            };
+            high_scores.insert(mode, entry);
+        }
+
+        Ok(high_scores)
+    }
+}
+
+/// Writes a `[mode]` line for each table followed by its scores, one per line as score,
+/// lines, level and name.
+impl fmt::Display for HighScores {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} {}", MAGIC, VERSION)?;
+        for (mode, table) in &self.tables {
+            writeln!(f, "[{}]", mode)?;
+            for e in table {
+                writeln!(f, "{} {} {} {}", e.score, e.lines, e.level, e.name)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Keeps names on one line and within `MAX_NAME_LENGTH`.
+fn clean_name(name: &str) -> String {
+    let name = name
+        .chars()
+        .filter(|c| !c.is_control())
+        .take(MAX_NAME_LENGTH)
+        .collect::<String>();
+    match name.trim() {
+        "" => "?".to_string(),
+        name => name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn entry(name: &str, score: u32) -> Entry {
+        Entry {
+            name: name.to_string(),
+            score,
+            lines: score / 100,
+            level: 1,
+        }
+    }
+
+    #[test]
+    fn insert_test() {
+        let mut high_scores = HighScores::default();
+        assert!(!high_scores.qualifies("a", 0));
+        assert_eq!(high_scores.insert("a", entry("one", 500)), Some(1));
+        assert_eq!(high_scores.insert("a", entry("two", 700)), Some(1));
+        assert_eq!(high_scores.insert("a", entry("three", 500)), Some(3));
+        assert_eq!(high_scores.insert("b", entry("other", 100)), Some(1));
+
+        let names = |h: &HighScores| {
+            h.table("a")
+                .iter()
+                .map(|e| e.name.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&high_scores), vec!["two", "one", "three"]);
+
+        for score in 1..=TABLE_SIZE as u32 {
+            high_scores.insert("a", entry("more", score * 1000));
+        }
+        assert_eq!(high_scores.table("a").len(), TABLE_SIZE);
+        assert_eq!(high_scores.table("a")[0].score, 10_000);
+        assert!(!high_scores.qualifies("a", 1000));
+        assert!(high_scores.qualifies("a", 1001));
+        assert_eq!(high_scores.insert("a", entry("low", 900)), None);
+        assert_eq!(high_scores.table("b").len(), 1);
+        assert!(high_scores.table("c").is_empty());
+    }
+
+    #[test]
+    fn parse_test() {
+        let mut high_scores = HighScores::default();
+        high_scores.insert("guideline-7bag-10x20", entry("Ann Lee", 1200));
+        high_scores.insert("guideline-7bag-10x20", entry("\tBob\n", 300));
+        high_scores.insert("20g-tgm-10x20", entry("a very long name indeed", 50));
+
+        let text = high_scores.to_string();
+        assert_eq!(
+            text,
+            "tetris-highscores 1\n[20g-tgm-10x20]\n50 0 1 a very long\n\
+             [guideline-7bag-10x20]\n1200 12 1 Ann Lee\n300 3 1 Bob\n"
+        );
+        assert_eq!(HighScores::parse(&text), Ok(high_scores));
+
+        assert!(HighScores::parse("").is_err());
+        assert_eq!(
+            HighScores::parse("tetris-highscores 1\n100 1 1 x").unwrap_err(),
+            "score outside of a mode: 100 1 1 x".to_string()
+        );
+        assert_eq!(
+            HighScores::parse("tetris-highscores 1\n[a]\n100 x 1 x").unwrap_err(),
+            "invalid score: 100 x 1 x".to_string()
+        );
+    }
+}
@@ -0,0 +1,403 @@
+use crate::glyphs::{Glyphs, UNICODE};
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use std::fmt;
+use tetris::Input;
+
+/// Everything a key can do during the game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Left,
+    Right,
+    RotateLeft,
+    RotateRight,
+    Rotate180,
+    SoftDrop,
+    HardDrop,
+    Hold,
+    Ghost,
+    Pause,
+    Quit,
+    /// Shows the high scores from the start screen.
+    HighScores,
+}
+
+impl Action {
+    pub const ALL: [Action; 12] = [
+        Action::Left,
+        Action::Right,
+        Action::RotateLeft,
+        Action::RotateRight,
+        Action::Rotate180,
+        Action::SoftDrop,
+        Action::HardDrop,
+        Action::Hold,
+        Action::Ghost,
+        Action::Pause,
+        Action::Quit,
+        Action::HighScores,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.into_iter().find(|action| action.name() == name)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Left => "left",
+            Action::Right => "right",
+            Action::RotateLeft => "rotate_left",
+            Action::RotateRight => "rotate_right",
+            Action::Rotate180 => "rotate_180",
+            Action::SoftDrop => "soft_drop",
+            Action::HardDrop => "hard_drop",
+            Action::Hold => "hold",
+            Action::Ghost => "ghost",
+            Action::Pause => "pause",
+            Action::Quit => "quit",
+            Action::HighScores => "high_scores",
+        }
+    }
+
+    /// What the help screen says the action does.
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::Left => "Move to the left",
+            Action::Right => "Move to the right",
+            Action::RotateLeft => "Rotate 90° counterclockwise",
+            Action::RotateRight => "Rotate 90° clockwise",
+            Action::Rotate180 => "Rotate 180°",
+            Action::SoftDrop => "soft drop",
+            Action::HardDrop => "drop down",
+            Action::Hold => "hold piece",
+            Action::Ghost => "show or hide the ghost piece",
+            Action::Pause => "pause or resume",
+            Action::Quit => "quit the game",
+            Action::HighScores => "show the high scores from this screen",
+        }
+    }
+
+    /// Whether the action works during the game.
+    pub fn in_game(&self) -> bool {
+        *self != Action::HighScores
+    }
+
+    /// Whether the action works on the start screen, where left and right pick the level.
+    pub fn on_start_screen(&self) -> bool {
+        matches!(self, Action::Left | Action::Right | Action::HighScores)
+    }
+
+    /// The game input of the action, none for the ones the front-end handles itself.
+    pub fn input(&self) -> Option<Input> {
+        match self {
+            Action::Left => Some(Input::Left),
+            Action::Right => Some(Input::Right),
+            Action::RotateLeft => Some(Input::RotateLeft),
+            Action::RotateRight => Some(Input::RotateRight),
+            Action::Rotate180 => Some(Input::Rotate180),
+            Action::SoftDrop => Some(Input::SoftDrop),
+            Action::HardDrop => Some(Input::HardDrop),
+            Action::Hold => Some(Input::Hold),
+            Action::Ghost | Action::Pause | Action::Quit | Action::HighScores => None,
+        }
+    }
+}
+
+/// A key together with the modifiers held down with it, such as ctrl+z.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Key {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl Key {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Key {
+        // Terminals report shifted letters either as upper case or with the shift modifier,
+        // and shifted symbols with or without it.
+        let (code, modifiers) = match code {
+            KeyCode::Char(c) if c.is_ascii_uppercase() => (
+                KeyCode::Char(c.to_ascii_lowercase()),
+                modifiers | KeyModifiers::SHIFT,
+            ),
+            KeyCode::Char(c) if c.is_ascii_punctuation() => (code, modifiers - KeyModifiers::SHIFT),
+            _ => (code, modifiers),
+        };
+        let modifiers =
+            modifiers & (KeyModifiers::SHIFT | KeyModifiers::CONTROL | KeyModifiers::ALT);
+        Key { code, modifiers }
+    }
+
+    /// Reads a key such as "x", "left", "space", "f5" or "ctrl+shift+z".
+    pub fn from_name(name: &str) -> Option<Key> {
+        let mut parts = name.split('+').collect::<Vec<_>>();
+        // A lone "+" is the plus key rather than a modifier separator.
+        let code = match parts.pop()? {
+            "" if parts.last() == Some(&"") => {
+                parts.pop();
+                "+"
+            }
+            code => code,
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        for modifier in parts {
+            modifiers |= match modifier.to_ascii_lowercase().as_str() {
+                "shift" => KeyModifiers::SHIFT,
+                "ctrl" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                _ => return None,
+            };
+        }
+
+        let mut chars = code.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match code.to_ascii_lowercase().as_str() {
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "space" => KeyCode::Char(' '),
+                "enter" => KeyCode::Enter,
+                "esc" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backspace" => KeyCode::Backspace,
+                "insert" => KeyCode::Insert,
+                "delete" => KeyCode::Delete,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                name => match name.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                    Some(n @ 1..=12) => KeyCode::F(n),
+                    _ => return None,
+                },
+            },
+        };
+
+        Some(Key::new(code, modifiers))
+    }
+
+    /// Names the key for the help screen, such as "CTRL+Z" or "←".
+    pub fn label(&self, glyphs: &Glyphs) -> String {
+        let mut label = String::new();
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "CTRL+"),
+            (KeyModifiers::ALT, "ALT+"),
+            (KeyModifiers::SHIFT, "SHIFT+"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                label.push_str(name);
+            }
+        }
+
+        match self.code {
+            KeyCode::Char(' ') => label.push_str("SPACE"),
+            KeyCode::Char(c) => label.push(c.to_ascii_uppercase()),
+            KeyCode::Left => label.push_str(glyphs.arrows[0]),
+            KeyCode::Right => label.push_str(glyphs.arrows[1]),
+            KeyCode::Up => label.push_str(glyphs.arrows[2]),
+            KeyCode::Down => label.push_str(glyphs.arrows[3]),
+            KeyCode::PageUp => label.push_str("PAGE UP"),
+            KeyCode::PageDown => label.push_str("PAGE DOWN"),
+            KeyCode::F(n) => label.push_str(&format!("F{}", n)),
+            code => label.push_str(&format!("{:?}", code).to_uppercase()),
+        }
+        label
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.label(&UNICODE))
+    }
+}
+
+/// The keys bound to each action, several keys can do the same thing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keymap {
+    bindings: Vec<(Action, Vec<Key>)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let key = |code| Key::new(code, KeyModifiers::NONE);
+        let bindings = Action::ALL
+            .into_iter()
+            .map(|action| {
+                let code = match action {
+                    Action::Left => KeyCode::Left,
+                    Action::Right => KeyCode::Right,
+                    Action::RotateLeft => KeyCode::Up,
+                    Action::RotateRight => KeyCode::Char('x'),
+                    Action::Rotate180 => KeyCode::Char('v'),
+                    Action::SoftDrop => KeyCode::Down,
+                    Action::HardDrop => KeyCode::Char(' '),
+                    Action::Hold => KeyCode::Char('c'),
+                    Action::Ghost => KeyCode::Char('g'),
+                    Action::Pause => KeyCode::Char('p'),
+                    Action::Quit => KeyCode::Esc,
+                    Action::HighScores => KeyCode::Char('s'),
+                };
+                (action, vec![key(code)])
+            })
+            .collect();
+
+        Self { bindings }
+    }
+}
+
+impl Keymap {
+    /// The action of a key event during the game. Releases ignore the modifiers, which may
+    /// have been let go of first.
+    pub fn action(&self, event: &KeyEvent) -> Option<Action> {
+        self.find(event, Action::in_game)
+    }
+
+    /// The action of a key event on the start screen.
+    pub fn start_action(&self, event: &KeyEvent) -> Option<Action> {
+        self.find(event, Action::on_start_screen)
+    }
+
+    fn find(&self, event: &KeyEvent, filter: fn(&Action) -> bool) -> Option<Action> {
+        let key = Key::new(event.code, event.modifiers);
+        self.bindings
+            .iter()
+            .filter(|(action, _)| filter(action))
+            .find(|(_, keys)| {
+                keys.iter().any(|k| match event.kind {
+                    KeyEventKind::Release => k.code == key.code,
+                    _ => *k == key,
+                })
+            })
+            .map(|(action, _)| *action)
+    }
+
+    pub fn keys(&self, action: Action) -> &[Key] {
+        self.bindings
+            .iter()
+            .find(|(a, _)| *a == action)
+            .map_or(&[], |(_, keys)| keys.as_slice())
+    }
+
+    /// Replaces the keys of `action`.
+    pub fn bind(&mut self, action: Action, keys: Vec<Key>) {
+        for binding in self.bindings.iter_mut() {
+            if binding.0 == action {
+                binding.1 = keys.clone();
+            }
+        }
+    }
+
+    /// Checks that no key is bound to two actions which work at the same time and that every
+    /// action has a key.
+    pub fn validate(&self) -> Result<(), String> {
+        let together = |a: &Action, b: &Action| {
+            (a.in_game() && b.in_game()) || (a.on_start_screen() && b.on_start_screen())
+        };
+        for (i, (action, keys)) in self.bindings.iter().enumerate() {
+            if keys.is_empty() {
+                return Err(format!("no key is bound to {}", action.name()));
+            }
+            for key in keys {
+                if let Some((other, _)) = self.bindings[..i]
+                    .iter()
+                    .find(|(other, k)| together(action, other) && k.contains(key))
+                {
+                    return Err(format!(
+                        "{} is bound to both {} and {}",
+                        key,
+                        other.name(),
+                        action.name()
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::glyphs::ASCII;
+
+    fn event(code: KeyCode, modifiers: KeyModifiers, kind: KeyEventKind) -> KeyEvent {
+        KeyEvent::new_with_kind(code, modifiers, kind)
+    }
+
+    #[test]
+    fn from_name_test() {
+        let key = |name| Key::from_name(name).map(|k| k.to_string());
+        assert_eq!(key("x").as_deref(), Some("X"));
+        assert_eq!(key("X").as_deref(), Some("SHIFT+X"));
+        assert_eq!(key("ctrl+shift+z").as_deref(), Some("CTRL+SHIFT+Z"));
+        assert_eq!(key("Alt+Left").as_deref(), Some("ALT+\u{2190}"));
+        assert_eq!(key("space").as_deref(), Some("SPACE"));
+        assert_eq!(key("+").as_deref(), Some("+"));
+        assert_eq!(key("ctrl++").as_deref(), Some("CTRL++"));
+        assert_eq!(key("pagedown").as_deref(), Some("PAGE DOWN"));
+        assert_eq!(
+            Key::from_name("shift+down").unwrap().label(&ASCII),
+            "SHIFT+DOWN"
+        );
+        assert_eq!(key("esc").as_deref(), Some("ESC"));
+        assert_eq!(key("shift+F12").as_deref(), Some("SHIFT+F12"));
+        assert_eq!(key("f13"), None);
+        assert_eq!(key("super+x"), None);
+        assert_eq!(key("x+"), None);
+        assert_eq!(key("jump"), None);
+        assert_eq!(key(""), None);
+    }
+
+    #[test]
+    fn action_test() {
+        let mut keymap = Keymap::default();
+        let keys = ["left", "a", "h", "ctrl+b"].map(|k| Key::from_name(k).unwrap());
+        keymap.bind(Action::Left, keys.to_vec());
+        assert_eq!(keymap.validate(), Ok(()));
+        keymap.bind(Action::HighScores, vec![Key::from_name("h").unwrap()]);
+        assert!(keymap.validate().is_err());
+        // The start screen has no hold, so its keys may be shared.
+        keymap.bind(Action::HighScores, vec![Key::from_name("c").unwrap()]);
+        assert_eq!(keymap.validate(), Ok(()));
+        keymap.bind(Action::Rotate180, vec![Key::from_name("a").unwrap()]);
+        assert!(keymap.validate().is_err());
+        keymap.bind(Action::Rotate180, vec![Key::from_name("s").unwrap()]);
+        assert_eq!(keymap.validate(), Ok(()));
+
+        let press = |code, modifiers| event(code, modifiers, KeyEventKind::Press);
+        assert_eq!(
+            keymap.action(&press(KeyCode::Char('h'), KeyModifiers::NONE)),
+            Some(Action::Left)
+        );
+        assert_eq!(
+            keymap.action(&press(KeyCode::Char('b'), KeyModifiers::CONTROL)),
+            Some(Action::Left)
+        );
+        assert_eq!(
+            keymap.action(&press(KeyCode::Char('b'), KeyModifiers::NONE)),
+            None
+        );
+        assert_eq!(
+            keymap.action(&press(KeyCode::Char('H'), KeyModifiers::SHIFT)),
+            None
+        );
+        assert_eq!(
+            keymap.action(&event(
+                KeyCode::Char('b'),
+                KeyModifiers::NONE,
+                KeyEventKind::Release
+            )),
+            Some(Action::Left)
+        );
+        assert_eq!(
+            keymap.action(&press(KeyCode::Esc, KeyModifiers::NONE)),
+            Some(Action::Quit)
+        );
+        let c = press(KeyCode::Char('c'), KeyModifiers::NONE);
+        assert_eq!(keymap.action(&c), Some(Action::Hold));
+        assert_eq!(keymap.start_action(&c), Some(Action::HighScores));
+        assert_eq!(keymap.keys(Action::Left).len(), 4);
+    }
+}
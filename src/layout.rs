@@ -0,0 +1,128 @@
+use crate::glyphs::Glyphs;
+use tetris::Rules;
+
+/// Columns between the board and the boxes beside it.
+const GAP: u16 = 2;
+
+/// Rows under the game for the score and the action label.
+const STATUS_ROWS: u16 = 2;
+
+/// Columns the score line takes from the left of the board, room for a six digit score.
+const STATUS_WIDTH: u16 = 34;
+
+/// Where everything goes on a terminal of a given size, the board in the middle with the
+/// hold box on its left and the next shapes on its right.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Layout {
+    /// Columns and rows of the terminal.
+    pub size: (u16, u16),
+    /// The left column of everything drawn, the hold box or else the board.
+    pub left: u16,
+    /// Top left corners of the board's border and of the boxes.
+    pub board: (u16, u16),
+    pub hold: Option<(u16, u16)>,
+    pub next: Option<(u16, u16)>,
+    /// Row of the score under the board and the boxes, followed by the action label and
+    /// then `footer` rows.
+    pub status: u16,
+}
+
+impl Layout {
+    /// Centres the game on a terminal of `size` with `footer` more rows under the status,
+    /// or returns the size it needs when that is too small.
+    pub fn new(
+        rules: &Rules,
+        glyphs: &Glyphs,
+        footer: u16,
+        size: (u16, u16),
+    ) -> Result<Layout, (u16, u16)> {
+        let board_width = glyphs.row_width(rules.width) as u16 + 2;
+        let board_height = rules.height as u16 + 2;
+        let hold = rules.hold.then(|| box_size(glyphs, 1));
+        let next = (rules.preview > 0).then(|| box_size(glyphs, rules.preview));
+
+        let side = |b: Option<(u16, u16)>| b.map_or(0, |(width, _)| width + GAP);
+        let width = side(hold) + (board_width + side(next)).max(STATUS_WIDTH);
+        let rows = [hold, next]
+            .iter()
+            .flatten()
+            .map(|(_, height)| *height)
+            .fold(board_height, u16::max);
+        let height = rows + STATUS_ROWS + footer;
+        if size.0 < width || size.1 < height {
+            return Err((width, height));
+        }
+
+        let left = (size.0 - width) / 2;
+        let top = (size.1 - height) / 2;
+        let board = (left + side(hold), top);
+        Ok(Layout {
+            size,
+            left,
+            board,
+            hold: hold.map(|_| (left, top)),
+            next: next.map(|_| (board.0 + board_width + GAP, top)),
+            status: top + rows,
+        })
+    }
+
+    /// The first of the `footer` rows under the score and the action label.
+    pub fn footer(&self) -> u16 {
+        self.status + STATUS_ROWS
+    }
+}
+
+/// Columns and rows of a box showing `shapes` shapes, each four cells wide.
+pub fn box_size(glyphs: &Glyphs, shapes: usize) -> (u16, u16) {
+    (glyphs.row_width(4) as u16 + 4, shapes as u16 * 3 + 1)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::glyphs::{ASCII, UNICODE};
+
+    #[test]
+    fn layout_test() {
+        let rules = Rules::default();
+        // The boxes are 11 columns wide, the board 21, and the next box 16 rows high.
+        let layout = Layout::new(&rules, &UNICODE, 0, (80, 24)).unwrap();
+        assert_eq!(layout.left, 16);
+        assert_eq!(layout.board, (29, 0));
+        assert_eq!(layout.hold, Some((16, 0)));
+        assert_eq!(layout.next, Some((52, 0)));
+        assert_eq!(layout.status, 22);
+
+        let layout = Layout::new(&rules, &UNICODE, 2, (100, 40)).unwrap();
+        assert_eq!(layout.board, (39, 7));
+        assert_eq!(layout.status, 29);
+        assert_eq!(layout.footer(), 31);
+
+        assert_eq!(Layout::new(&rules, &UNICODE, 2, (80, 24)), Err((47, 26)));
+        assert_eq!(Layout::new(&rules, &ASCII, 0, (40, 30)), Err((50, 24)));
+
+        // The score goes under the next box when that is taller than the board.
+        let short = Rules {
+            height: 10,
+            ..rules.clone()
+        };
+        let layout = Layout::new(&short, &UNICODE, 0, (80, 40)).unwrap();
+        let next = layout.next.unwrap();
+        assert_eq!(layout.status, next.1 + box_size(&UNICODE, 5).1);
+        assert_eq!(layout.status, 27);
+
+        // A narrow board leaves room for the score beside it.
+        let rules = Rules {
+            hold: false,
+            preview: 0,
+            width: 4,
+            height: 10,
+            ..rules
+        };
+        assert_eq!(Layout::new(&rules, &ASCII, 0, (22, 14)), Err((34, 14)));
+        let layout = Layout::new(&rules, &ASCII, 0, (34, 14)).unwrap();
+        assert_eq!(layout.board, (0, 0));
+        assert_eq!(layout.hold, None);
+        assert_eq!(layout.next, None);
+    }
+}
@@ -0,0 +1,21 @@
+pub mod board;
+mod game;
+pub mod gravity;
+mod handling;
+pub mod highscores;
+pub mod randomizer;
+mod replay;
+mod rng;
+mod rules;
+pub mod scoring;
+pub mod shapes;
+
+pub use board::Board;
+pub use game::{GameEvent, GameState, Input, Phase, FRAME_RATE};
+pub use gravity::{Gravity, GravityCurve};
+pub use handling::{Controller, Handling, InputEvent};
+pub use highscores::HighScores;
+pub use randomizer::{Randomizer, RandomizerKind};
+pub use replay::{Playback, Replay};
+pub use rng::Rng;
+pub use rules::{LockReset, Rules, MAX_START_LEVEL};
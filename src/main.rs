@@ -1,251 +1,889 @@
+mod config;
+mod glyphs;
+mod keymap;
+mod layout;
+mod theme;
+
+use config::Config;
 use crossterm::{
     cursor,
-    event::{read, Event, EventStream, KeyCode, KeyEvent},
+    event::{
+        read, DisableFocusChange, EnableFocusChange, Event, EventStream, KeyCode, KeyEvent,
+        KeyEventKind, KeyboardEnhancementFlags, PopKeyboardEnhancementFlags,
+        PushKeyboardEnhancementFlags,
+    },
     style::{self, Stylize},
-    terminal::{disable_raw_mode, enable_raw_mode, Clear, ClearType},
-    ExecutableCommand, QueueableCommand, Result,
+    terminal::{
+        self, disable_raw_mode, enable_raw_mode, supports_keyboard_enhancement, Clear, ClearType,
+    },
+    ExecutableCommand, QueueableCommand,
 };
 use futures::{executor, select, FutureExt, StreamExt};
 use futures_timer::Delay;
-use rand::{thread_rng, Rng};
+use glyphs::Glyphs;
+use keymap::{Action, Keymap};
+use layout::Layout;
 use std::{
-    io::{stdout, Write},
-    time::Duration,
+    env, fs,
+    io::{stdout, ErrorKind, Result, Stdout, Write},
+    path::PathBuf,
+    process,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tetris::{
+    board::{cell_value, Board},
+    highscores::{Entry, MAX_NAME_LENGTH},
+    randomizer::FixedSequence,
+    scoring::TSpin,
+    shapes::{Direction, Shape, ShapeType},
+    Controller, GameEvent, GameState, GravityCurve, Handling, HighScores, Input, InputEvent,
+    LockReset, Phase, Playback, RandomizerKind, Replay, Rules, FRAME_RATE, MAX_START_LEVEL,
 };
+use theme::{ColorDepth, Theme};
 
-mod shapes;
+fn print_cell(stdout: &mut Stdout, theme: &Theme, glyphs: &Glyphs, cell: u8) {
+    match cell {
+        1..=7 => stdout.queue(style::PrintStyledContent(
+            glyphs.block.with(theme.piece(cell)),
+        )),
+        _ => stdout.queue(style::Print(glyphs.empty)),
+    }
+    .unwrap();
+}
 
-use shapes::{Direction, Position, Shape, ShapeType};
+fn print_shape_box(
+    theme: &Theme,
+    glyphs: &Glyphs,
+    col: u16,
+    row: u16,
+    title: &str,
+    shape_types: &[Option<ShapeType>],
+) {
+    let width = layout::box_size(glyphs, shape_types.len()).0 as usize - 2;
+    let mut stdout = stdout();
+    stdout.queue(cursor::MoveTo(col, row)).unwrap();
+    stdout
+        .queue(style::PrintStyledContent(
+            glyphs.top(title, width).with(theme.border),
+        ))
+        .unwrap();
 
-fn validate(board: &[[u8; 10]], shape_pos: &[Position; 4]) -> bool {
-    for p in shape_pos {
-        if p.x >= 10 || p.y >= board.len() || board[p.y][p.x] != 0 {
-            return false;
+    // Every shape takes two rows, with an empty row between neighbours.
+    let shapes = shape_types
+        .iter()
+        .map(|t| t.map(|t| Shape::new(t, Direction::Top, 4)))
+        .collect::<Vec<_>>();
+    let height = shapes.len() as u16 * 3 - 1;
+    for y in 0..height {
+        stdout.queue(cursor::MoveTo(col, row + 1 + y)).unwrap();
+        stdout
+            .queue(style::PrintStyledContent(
+                format!("{} ", glyphs.vertical).with(theme.border),
+            ))
+            .unwrap();
+        let shape = shapes[y as usize / 3].as_ref().filter(|_| y % 3 < 2);
+        for x in 0..4 {
+            let cell = shape
+                .filter(|s| s.body.iter().any(|p| p.x == x && p.y == (y % 3) as usize))
+                .map_or(0, |s| cell_value(s.shape_type));
+            print_cell(&mut stdout, theme, glyphs, cell);
+            if x < 3 {
+                stdout.queue(style::Print(glyphs.gap)).unwrap();
+            }
         }
+        stdout
+            .queue(style::PrintStyledContent(
+                format!(" {}", glyphs.vertical).with(theme.border),
+            ))
+            .unwrap();
     }
 
-    true
+    stdout.queue(cursor::MoveTo(col, row + 1 + height)).unwrap();
+    stdout
+        .queue(style::PrintStyledContent(
+            glyphs.bottom(width).with(theme.border),
+        ))
+        .unwrap();
 }
 
-fn change(board: &mut [[u8; 10]], shape: &Shape, occupied: bool) {
-    shape.body.iter().for_each(|p| {
-        board[p.y][p.x] = if occupied {
-            match shape.shape_type {
-                ShapeType::TForm => 1,
-                ShapeType::LForm => 2,
-                ShapeType::LMirrored => 3,
-                ShapeType::Line => 4,
-                ShapeType::SForm => 5,
-                ShapeType::SMirrored => 6,
-                ShapeType::Quadrat => 7,
-            }
-        } else {
-            0
-        }
-    })
+/// The name of a special clear for the action label, like "T-SPIN DOUBLE".
+fn action_label(events: &[GameEvent]) -> Option<String> {
+    let (t_spin, lines) = events.iter().find_map(|e| match e {
+        GameEvent::TSpin(t_spin, lines) => Some((*t_spin, *lines)),
+        _ => None,
+    })?;
+    let mut label = match t_spin {
+        TSpin::Mini => "MINI T-SPIN".to_string(),
+        TSpin::Full => "T-SPIN".to_string(),
+    };
+    if let Some(name) = ["", " SINGLE", " DOUBLE", " TRIPLE"].get(lines as usize) {
+        label.push_str(name);
+    }
+    if events.contains(&GameEvent::BackToBack) {
+        label.insert_str(0, "B2B ");
+    }
+    Some(label)
 }
 
-fn print_board(board: &[[u8; 10]], score: u32, speed: u64) {
+/// Draws the game, or while `paused` only its frame so the stack cannot be studied.
+fn print_board(
+    state: &GameState,
+    theme: &Theme,
+    glyphs: &Glyphs,
+    layout: &Layout,
+    show_ghost: bool,
+    action: Option<&str>,
+    paused: bool,
+) {
+    let board = match paused {
+        true => Board::new(state.board().width(), state.board().height()),
+        false => state.cells(),
+    };
+    let ghost =
+        Some(state.ghost()).filter(|_| show_ghost && !paused && state.phase() == &Phase::Falling);
+    // Completed rows flash and dissolve from the middle outwards while they clear.
+    let half = (board.width() as u32).div_ceil(2);
+    let (clearing, dissolved) = match state.phase() {
+        Phase::LineClear {
+            rows,
+            elapsed,
+            duration,
+        } if !paused => (rows.as_slice(), elapsed * half / duration),
+        _ => (&[][..], 0),
+    };
+    let width = glyphs.row_width(board.width());
+    let (left, top) = layout.board;
     let mut row = 0;
     let mut stdout = stdout();
-    stdout.queue(cursor::MoveTo(0, row)).unwrap();
+    stdout.queue(cursor::MoveTo(left, top + row)).unwrap();
     stdout
         .queue(style::PrintStyledContent(
-            format!("\u{250C}{:\u{2500}>20}", "\u{2510}").yellow(),
+            glyphs.top("", width).with(theme.border),
         ))
         .unwrap();
-    for line in board.iter().skip(2) {
+    for (y, line) in board.rows().enumerate().skip(board.hidden_rows()) {
         row += 1;
-        stdout.queue(cursor::MoveTo(0, row)).unwrap();
+        stdout.queue(cursor::MoveTo(left, top + row)).unwrap();
         stdout
-            .queue(style::PrintStyledContent("\u{2502}".yellow()))
+            .queue(style::PrintStyledContent(
+                glyphs.vertical.to_string().with(theme.border),
+            ))
             .unwrap();
         for (i, cell) in line.iter().enumerate() {
-            match cell {
-                1 => stdout.queue(style::PrintStyledContent("\u{2587}".dark_cyan())),
-                2 => stdout.queue(style::PrintStyledContent("\u{2587}".dark_green())),
-                3 => stdout.queue(style::PrintStyledContent("\u{2587}".dark_red())),
-                4 => stdout.queue(style::PrintStyledContent("\u{2587}".dark_blue())),
-                5 => stdout.queue(style::PrintStyledContent("\u{2587}".dark_magenta())),
-                6 => stdout.queue(style::PrintStyledContent("\u{2587}".red())),
-                7 => stdout.queue(style::PrintStyledContent("\u{2587}".dark_yellow())),
-                _ => stdout.queue(style::Print(" ")),
+            if clearing.contains(&y) {
+                let from_middle = half - 1 - i.min(line.len() - 1 - i) as u32;
+                match from_middle < dissolved {
+                    true => print_cell(&mut stdout, theme, glyphs, 0),
+                    false => {
+                        stdout
+                            .queue(style::PrintStyledContent(glyphs.block.with(theme.clear)))
+                            .unwrap();
+                    }
+                }
+            } else if *cell == 0
+                && ghost
+                    .as_ref()
+                    .filter(|s| s.body.iter().any(|p| p.x == i && p.y == y))
+                    .is_some()
+            {
+                stdout
+                    .queue(style::PrintStyledContent(glyphs.ghost.with(theme.ghost)))
+                    .unwrap();
+            } else {
+                print_cell(&mut stdout, theme, glyphs, *cell);
             }
-            .unwrap();
 
             if i < line.len() - 1 {
-                stdout.queue(style::Print(" ")).unwrap();
+                stdout.queue(style::Print(glyphs.gap)).unwrap();
             }
         }
 
         stdout
-            .queue(style::PrintStyledContent("\u{2502}".yellow()))
+            .queue(style::PrintStyledContent(
+                glyphs.vertical.to_string().with(theme.border),
+            ))
             .unwrap();
     }
 
     row += 1;
-    stdout.queue(cursor::MoveTo(0, row)).unwrap();
+    stdout.queue(cursor::MoveTo(left, top + row)).unwrap();
     stdout
         .queue(style::PrintStyledContent(
-            format!("\u{2514}{:\u{2500}>20}", "\u{2518}").yellow(),
+            glyphs.bottom(width).with(theme.border),
         ))
         .unwrap();
 
-    row += 2;
-    stdout.queue(cursor::MoveTo(0, row)).unwrap();
+    if paused {
+        let text = "PAUSED";
+        stdout
+            .queue(cursor::MoveTo(
+                left + ((width + 3 - text.len()) / 2) as u16,
+                top + board.height() as u16 / 2 + 1,
+            ))
+            .unwrap();
+        stdout
+            .queue(style::PrintStyledContent(text.with(theme.value).bold()))
+            .unwrap();
+    }
+
+    stdout.queue(cursor::MoveTo(left, layout.status)).unwrap();
+    stdout
+        .queue(style::PrintStyledContent("Score: ".with(theme.text)))
+        .unwrap();
     stdout
-        .queue(style::PrintStyledContent("Score: ".dark_green()))
+        .queue(style::PrintStyledContent(
+            format!("{}", state.score()).with(theme.value),
+        ))
         .unwrap();
     stdout
-        .queue(style::PrintStyledContent(format!("{}", score).dark_red()))
+        .queue(style::PrintStyledContent(" Lines: ".with(theme.text)))
         .unwrap();
     stdout
-        .queue(style::PrintStyledContent(" Speed: ".dark_green()))
+        .queue(style::PrintStyledContent(
+            format!("{}", state.lines()).with(theme.value),
+        ))
+        .unwrap();
+    stdout
+        .queue(style::PrintStyledContent(" Level: ".with(theme.text)))
         .unwrap();
     stdout
         .queue(style::PrintStyledContent(
-            format!("{}", 1100 - speed).dark_red(),
+            format!("{}", state.level()).with(theme.value),
         ))
         .unwrap();
+    // Seeking back in a replay lowers the numbers, which would leave digits behind.
+    stdout.queue(Clear(ClearType::UntilNewLine)).unwrap();
+
+    stdout
+        .queue(cursor::MoveTo(left, layout.status + 1))
+        .unwrap();
+    stdout
+        .queue(style::PrintStyledContent(
+            format!("{:1$}", action.unwrap_or_default(), width + 1).with(theme.action),
+        ))
+        .unwrap();
+
+    if let Some((col, row)) = layout.hold {
+        print_shape_box(
+            theme,
+            glyphs,
+            col,
+            row,
+            " Hold ",
+            &[state.hold().filter(|_| !paused)],
+        );
+    }
+    if let Some((col, row)) = layout.next {
+        let next = state
+            .next_shapes()
+            .map(|s| Some(s).filter(|_| !paused))
+            .collect::<Vec<_>>();
+        print_shape_box(theme, glyphs, col, row, " Next ", &next);
+    }
     stdout.flush().unwrap();
 }
 
-fn remove_completed_lines(board: &mut [[u8; 10]]) -> u32 {
-    let mut score = 0_u32;
-    let mut add = 10;
-    for y in 0..board.len() {
-        if !board[y].iter().any(|n| n == &0) {
-            let mut prev = y;
-            for b in (0..prev).rev() {
-                board[prev] = board[b];
-                prev = b;
+/// Shows the size the game needs in place of the game.
+fn print_too_small(theme: &Theme, size: (u16, u16), needed: (u16, u16)) {
+    let lines = [
+        "Terminal too small".to_string(),
+        format!(
+            "{}x{} is needed, it is {}x{}",
+            needed.0, needed.1, size.0, size.1
+        ),
+    ];
+    let mut stdout = stdout();
+    for (i, line) in lines.iter().enumerate() {
+        let line = line.chars().take(size.0 as usize).collect::<String>();
+        stdout
+            .queue(cursor::MoveTo(
+                size.0.saturating_sub(line.len() as u16) / 2,
+                (size.1 / 2).saturating_sub(1) + i as u16,
+            ))
+            .unwrap();
+        stdout
+            .queue(style::PrintStyledContent(line.with(theme.value).bold()))
+            .unwrap();
+    }
+    stdout.flush().unwrap();
+}
+
+struct Options {
+    seed: u64,
+    rules: Rules,
+    handling: Handling,
+    ghost: bool,
+    theme: Theme,
+    glyphs: &'static Glyphs,
+    keys: Keymap,
+    replay: Option<Replay>,
+}
+
+/// Reads the file given by `--config`, or else the one in the user's config directory.
+fn load_config(args: &[String]) -> std::result::Result<Config, String> {
+    match args.iter().position(|arg| arg == "--config") {
+        Some(i) => {
+            let path = args.get(i + 1).ok_or("--config requires a file")?;
+            let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+            config::parse(&text).map_err(|e| format!("{}: {}", path, e))
+        }
+        None => match dirs::config_dir() {
+            Some(dir) => config::load(&dir.join("tetris").join("config.toml")),
+            None => Ok(Config::default()),
+        },
+    }
+}
+
+fn parse_args() -> std::result::Result<Options, String> {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let Config {
+        mut rules,
+        mut handling,
+        mut ghost,
+        theme,
+        colors,
+        mut glyphs,
+        keys,
+    } = load_config(&args)?;
+    let mut seed = None;
+    let mut replay = None;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--config" => {
+                args.next();
             }
-            board[0] = [0; 10];
-            score += add;
-            add *= 2;
+            "--replay" => {
+                let path = args.next().ok_or("--replay requires a file")?;
+                let text = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path, e))?;
+                replay = Some(Replay::parse(&text).map_err(|e| format!("{}: {}", path, e))?);
+            }
+            "--seed" => {
+                let value = args.next().ok_or("--seed requires a value")?;
+                seed = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("invalid seed: {}", value))?,
+                );
+            }
+            "--randomizer" => {
+                let value = args.next().ok_or("--randomizer requires a value")?;
+                rules.randomizer = RandomizerKind::from_name(&value)
+                    .ok_or_else(|| format!("unknown randomizer: {}", value))?;
+            }
+            "--sequence" => {
+                let path = args.next().ok_or("--sequence requires a file")?;
+                let text = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path, e))?;
+                let sequence =
+                    FixedSequence::parse(&text).map_err(|e| format!("{}: {}", path, e))?;
+                rules.randomizer = RandomizerKind::Fixed(sequence);
+            }
+            "--width" => {
+                let value = args.next().ok_or("--width requires a value")?;
+                rules.width = value
+                    .parse()
+                    .map_err(|_| format!("invalid width: {}", value))?;
+            }
+            "--height" => {
+                let value = args.next().ok_or("--height requires a value")?;
+                rules.height = value
+                    .parse()
+                    .map_err(|_| format!("invalid height: {}", value))?;
+            }
+            "--no-hold" => rules.hold = false,
+            "--no-ghost" => ghost = false,
+            "--ascii" => glyphs = Some(&glyphs::ASCII),
+            "--level" => {
+                let value = args.next().ok_or("--level requires a value")?;
+                rules.start_level = value
+                    .parse()
+                    .map_err(|_| format!("invalid level: {}", value))?;
+            }
+            "--lock-delay" => {
+                let value = args.next().ok_or("--lock-delay requires a value")?;
+                rules.lock_delay = value
+                    .parse()
+                    .map_err(|_| format!("invalid lock delay: {}", value))?;
+            }
+            "--lock-reset" => {
+                let value = args.next().ok_or("--lock-reset requires a value")?;
+                rules.lock_reset = LockReset::from_name(&value)
+                    .ok_or_else(|| format!("unknown lock reset: {}", value))?;
+            }
+            "--das" => {
+                let value = args.next().ok_or("--das requires a value")?;
+                handling.das = value
+                    .parse()
+                    .map_err(|_| format!("invalid DAS: {}", value))?;
+            }
+            "--arr" => {
+                let value = args.next().ok_or("--arr requires a value")?;
+                handling.arr = value
+                    .parse()
+                    .map_err(|_| format!("invalid ARR: {}", value))?;
+            }
+            "--soft-drop-factor" => {
+                let value = args.next().ok_or("--soft-drop-factor requires a value")?;
+                handling.soft_drop_factor = value
+                    .parse()
+                    .map_err(|_| format!("invalid soft drop factor: {}", value))?;
+            }
+            "--line-clear-delay" => {
+                let value = args.next().ok_or("--line-clear-delay requires a value")?;
+                rules.line_clear_delay = value
+                    .parse()
+                    .map_err(|_| format!("invalid line clear delay: {}", value))?;
+            }
+            "--are" => {
+                let value = args.next().ok_or("--are requires a value")?;
+                rules.entry_delay = value
+                    .parse()
+                    .map_err(|_| format!("invalid entry delay: {}", value))?;
+            }
+            "--gravity" => {
+                let value = args.next().ok_or("--gravity requires a value")?;
+                rules.gravity = GravityCurve::from_name(&value)
+                    .ok_or_else(|| format!("unknown gravity: {}", value))?;
+            }
+            "--preview" => {
+                let value = args.next().ok_or("--preview requires a value")?;
+                rules.preview = value
+                    .parse()
+                    .map_err(|_| format!("invalid preview length: {}", value))?;
+            }
+            _ => return Err(format!("unknown argument: {}", arg)),
         }
     }
 
-    score
+    rules.validate()?;
+    handling.validate()?;
+
+    let seed = seed.unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or_default()
+    });
+
+    Ok(Options {
+        seed,
+        rules,
+        handling,
+        ghost,
+        theme: theme.downgrade(colors.unwrap_or_else(ColorDepth::detect)),
+        glyphs: glyphs.unwrap_or_else(Glyphs::detect),
+        keys,
+        replay,
+    })
+}
+
+/// Hands a key press or release to the game, recording it in the replay.
+fn send(
+    state: &mut GameState,
+    controller: &mut Controller,
+    replay: &mut Replay,
+    event: InputEvent,
+) -> Vec<GameEvent> {
+    replay.record(state.frame(), event);
+    controller.handle(state, event)
 }
 
-fn move_shape_down(shape: Shape, board: &[[u8; 10]], steps: Option<usize>) -> Option<Shape> {
-    let mut steps = steps.unwrap_or(board.len());
-    let mut shape = Some(shape);
+/// Releases the held keys, whose releases will not be seen while the game is paused.
+fn pause(state: &mut GameState, controller: &mut Controller, replay: &mut Replay) {
+    for input in [Input::Left, Input::Right, Input::SoftDrop] {
+        send(state, controller, replay, InputEvent::Release(input));
+    }
+}
+
+async fn run_game(options: &Options, key_releases: bool) -> Result<(GameState, Replay)> {
+    let mut event_stream = EventStream::new();
+    let mut state = GameState::new(options.seed, options.rules.clone());
+    let mut controller = Controller::new(options.handling);
+    let mut replay = Replay::new(options.seed, options.rules.clone(), options.handling);
+    let mut show_ghost = options.ghost;
+    // The last special clear and the frame it stops showing on.
+    let mut action: Option<(String, u64)> = None;
+
+    let frame = Duration::from_secs(1) / FRAME_RATE;
+    let start = Instant::now();
+    let mut next_frame = Delay::new(frame).fuse();
+    // When the current pause began and how long the earlier ones lasted.
+    let mut paused_at: Option<Instant> = None;
+    let mut paused_for = Duration::ZERO;
+    // Whether the pause is only for a terminal too small for the game.
+    let mut too_small = false;
+    let mut size = terminal::size()?;
+
+    while !state.is_game_over() {
+        let mut next_event = event_stream.next().fuse();
+        let layout = Layout::new(&options.rules, options.glyphs, 0, size);
+        // The game cannot be played unseen, it waits until it fits again.
+        match (&layout, paused_at) {
+            (Err(_), None) => {
+                pause(&mut state, &mut controller, &mut replay);
+                paused_at = Some(Instant::now());
+                too_small = true;
+            }
+            (Ok(_), Some(at)) if too_small => {
+                paused_for += at.elapsed();
+                paused_at = None;
+                too_small = false;
+            }
+            _ => (),
+        }
+
+        if action
+            .as_ref()
+            .is_some_and(|(_, until)| state.frame() >= *until)
+        {
+            action = None;
+        }
+        match layout {
+            Ok(layout) => print_board(
+                &state,
+                &options.theme,
+                options.glyphs,
+                &layout,
+                show_ghost,
+                action.as_ref().map(|(a, _)| a.as_str()),
+                paused_at.is_some(),
+            ),
+            Err(needed) => print_too_small(&options.theme, size, needed),
+        }
+
+        let mut events = Vec::new();
+        select! {
+            _ = next_frame => {
+                next_frame = Delay::new(frame).fuse();
+                if paused_at.is_some() {
+                    continue;
+                }
 
-    while steps > 0 {
-        steps -= 1;
-        let next = shape.as_ref().and_then(|s| s.down());
+                // Timers fire late now and then, catch up on the frames that are due.
+                let played = start.elapsed() - paused_for;
+                let due = played.as_millis() as u64 * FRAME_RATE as u64 / 1000;
+                while state.frame() < due && !state.is_game_over() {
+                    events.extend(controller.tick(&mut state));
+                }
+            },
+            event = next_event => {
+                let key = match event {
+                    Some(Ok(Event::Key(key))) => key,
+                    Some(Ok(Event::FocusLost)) if paused_at.is_none() => {
+                        pause(&mut state, &mut controller, &mut replay);
+                        paused_at = Some(Instant::now());
+                        continue;
+                    }
+                    Some(Ok(Event::Resize(columns, rows))) => {
+                        size = (columns, rows);
+                        stdout().execute(Clear(ClearType::All)).unwrap();
+                        continue;
+                    }
+                    _ => continue,
+                };
+                let kind = key.kind;
+                let Some(action) = options.keys.action(&key) else {
+                    continue;
+                };
+                match (action, kind) {
+                    (Action::Pause, KeyEventKind::Press) => {
+                        too_small = false;
+                        match paused_at.take() {
+                            Some(at) => paused_for += at.elapsed(),
+                            None => {
+                                pause(&mut state, &mut controller, &mut replay);
+                                paused_at = Some(Instant::now());
+                            }
+                        }
+                        continue;
+                    }
+                    (Action::Quit, KeyEventKind::Press) => break,
+                    _ if paused_at.is_some() => continue,
+                    (Action::Ghost, KeyEventKind::Press) => {
+                        show_ghost = !show_ghost;
+                        continue;
+                    }
+                    _ => (),
+                }
+                let Some(input) = action.input() else {
+                    continue;
+                };
+                let (state, controller, replay) = (&mut state, &mut controller, &mut replay);
+                match kind {
+                    KeyEventKind::Press => {
+                        events = send(state, controller, replay, InputEvent::Press(input));
+                        // Without release events every press is a tap, the terminal's own
+                        // key repeat keeps a held key going.
+                        if !key_releases {
+                            send(state, controller, replay, InputEvent::Release(input));
+                        }
+                    }
+                    KeyEventKind::Release => {
+                        send(state, controller, replay, InputEvent::Release(input));
+                    }
+                    KeyEventKind::Repeat => (),
+                }
+            },
+        };
 
-        if next.as_ref().filter(|s| validate(board, &s.body)).is_some() {
-            shape = next;
-        } else {
-            break;
+        if let Some(label) = action_label(&events) {
+            action = Some((label, state.frame() + 2 * FRAME_RATE as u64));
         }
     }
 
-    shape
+    replay.frames = state.frame();
+    replay.score = state.score();
+    Ok((state, replay))
 }
 
-fn random_shape() -> Shape {
-    let nr = thread_rng().gen_range(0..=27);
-    match nr {
-        0 => Shape::new(ShapeType::SForm, Direction::Top),
-        1 => Shape::new(ShapeType::Quadrat, Direction::Top),
-        2 => Shape::new(ShapeType::LForm, Direction::Top),
-        3 => Shape::new(ShapeType::Line, Direction::Top),
-        4 => Shape::new(ShapeType::TForm, Direction::Top),
-        5 => Shape::new(ShapeType::SMirrored, Direction::Top),
-        6 => Shape::new(ShapeType::LMirrored, Direction::Top),
-        7 => Shape::new(ShapeType::SForm, Direction::Left),
-        8 => Shape::new(ShapeType::Quadrat, Direction::Left),
-        9 => Shape::new(ShapeType::LForm, Direction::Left),
-        10 => Shape::new(ShapeType::Line, Direction::Left),
-        11 => Shape::new(ShapeType::TForm, Direction::Left),
-        12 => Shape::new(ShapeType::SMirrored, Direction::Left),
-        13 => Shape::new(ShapeType::LMirrored, Direction::Left),
-        14 => Shape::new(ShapeType::SForm, Direction::Bottom),
-        15 => Shape::new(ShapeType::Quadrat, Direction::Bottom),
-        16 => Shape::new(ShapeType::LForm, Direction::Bottom),
-        17 => Shape::new(ShapeType::Line, Direction::Bottom),
-        18 => Shape::new(ShapeType::TForm, Direction::Bottom),
-        19 => Shape::new(ShapeType::SMirrored, Direction::Bottom),
-        20 => Shape::new(ShapeType::LMirrored, Direction::Bottom),
-        21 => Shape::new(ShapeType::SForm, Direction::Right),
-        22 => Shape::new(ShapeType::Quadrat, Direction::Right),
-        23 => Shape::new(ShapeType::LForm, Direction::Right),
-        24 => Shape::new(ShapeType::Line, Direction::Right),
-        25 => Shape::new(ShapeType::TForm, Direction::Right),
-        26 => Shape::new(ShapeType::SMirrored, Direction::Right),
-        _ => Shape::new(ShapeType::LMirrored, Direction::Right),
+/// Playback speeds as fractions of real time, from a quarter to eight times as fast.
+const SPEEDS: [(u64, u64); 6] = [(1, 4), (1, 2), (1, 1), (2, 1), (4, 1), (8, 1)];
+
+/// Frames skipped by seeking with the arrow keys.
+const SEEK_STEP: u64 = 5 * FRAME_RATE as u64;
+
+/// Rows under the game for the playback status and the playback keys.
+const PLAYBACK_ROWS: u16 = 3;
+
+fn format_time(frame: u64) -> String {
+    let tenths = frame * 10 / FRAME_RATE as u64;
+    format!("{}:{:02}.{}", tenths / 600, tenths / 10 % 60, tenths % 10)
+}
+
+fn print_playback_status(
+    playback: &Playback,
+    options: &Options,
+    layout: &Layout,
+    speed: usize,
+    paused: bool,
+    target: &str,
+) {
+    let theme = &options.theme;
+    let glyphs = options.glyphs;
+    let state = playback.state();
+    let (col, row) = (layout.left, layout.footer());
+    // Long lines are cut at the edge of the terminal rather than wrapped over the board.
+    let fit = |line: String| {
+        let width = layout.size.0.saturating_sub(col) as usize;
+        line.chars().take(width).collect::<String>()
+    };
+    let (num, den) = SPEEDS[speed];
+    let speed = match den {
+        1 => format!("{}x", num),
+        _ => format!("{}/{}x", num, den),
+    };
+    let status = if playback.diverged() {
+        format!(
+            "DIVERGED, the recorded score was {}",
+            playback.replay().score
+        )
+    } else if playback.is_finished() {
+        "END".to_string()
+    } else if paused {
+        "PAUSED".to_string()
+    } else {
+        String::new()
+    };
+
+    let seek = match target.is_empty() {
+        true => String::new(),
+        false => format!("  Seek to {}_", target),
+    };
+    let lines = [
+        fit(format!(
+            "Replay {} / {}  Piece {}  {}  {}",
+            format_time(state.frame()),
+            format_time(playback.replay().frames),
+            playback.pieces() + 1,
+            speed,
+            status
+        ))
+        .with(theme.value),
+        fit(format!(
+            "{} pause  . step  +/- speed  {}/{} 5 seconds  {} quit",
+            options.keys.keys(Action::Pause)[0].label(glyphs),
+            glyphs.arrows[0],
+            glyphs.arrows[1],
+            options.keys.keys(Action::Quit)[0].label(glyphs)
+        ))
+        .with(theme.text),
+        fit(format!(
+            "NUMBER T seek to second  NUMBER N seek to piece{}",
+            seek
+        ))
+        .with(theme.text),
+    ];
+
+    let mut stdout = stdout();
+    for (i, line) in lines.into_iter().enumerate() {
+        stdout.queue(cursor::MoveTo(col, row + i as u16)).unwrap();
+        stdout.queue(style::PrintStyledContent(line)).unwrap();
+        stdout.queue(Clear(ClearType::UntilNewLine)).unwrap();
     }
+    stdout.flush().unwrap();
 }
 
-async fn run_game() -> Result<()> {
+/// Plays a recorded game back through the engine.
+async fn run_playback(replay: Replay, options: &Options) -> Result<()> {
+    let theme = &options.theme;
+    let glyphs = options.glyphs;
     let mut event_stream = EventStream::new();
-    let mut board = [[0_u8; 10]; 22];
-    let mut shape = random_shape();
-    let mut down_delay = 1000;
-    let speed_up_delay = 30;
-    let mut score = 0;
+    let mut playback = Playback::new(replay);
+    stdout().execute(Clear(ClearType::All)).unwrap();
+    let mut speed = 2;
+    let mut paused = false;
+    // Digits typed so far for a seek.
+    let mut target = String::new();
+    let mut action: Option<(String, u64)> = None;
 
-    let mut speed_up = Delay::new(Duration::from_secs(speed_up_delay)).fuse();
-    let mut down = Delay::new(Duration::from_millis(down_delay)).fuse();
+    let frame = Duration::from_secs(1) / FRAME_RATE;
+    let mut next_frame = Delay::new(frame).fuse();
+    // Playback is due to have reached this frame at this time, moved on every change of pace.
+    let mut anchor = (Instant::now(), 0);
+    let mut size = terminal::size()?;
 
     loop {
         let mut next_event = event_stream.next().fuse();
+        let layout = Layout::new(&playback.replay().rules, glyphs, PLAYBACK_ROWS, size);
 
-        change(&mut board, &shape, true);
-        print_board(&board, score, down_delay);
-        change(&mut board, &shape, false);
+        let state = playback.state();
+        if action
+            .as_ref()
+            .is_some_and(|(_, until)| state.frame() >= *until)
+        {
+            action = None;
+        }
+        match layout {
+            Ok(layout) => {
+                print_board(
+                    state,
+                    theme,
+                    glyphs,
+                    &layout,
+                    true,
+                    action.as_ref().map(|(a, _)| a.as_str()),
+                    false,
+                );
+                print_playback_status(&playback, options, &layout, speed, paused, &target);
+            }
+            Err(needed) => print_too_small(theme, size, needed),
+        }
 
+        let mut events = Vec::new();
         select! {
-            _ = speed_up => if down_delay > 100 {
-                down_delay -= 100;
-                speed_up = Delay::new(Duration::from_secs(speed_up_delay)).fuse();
-            },
-            _ = down => {
-                down = Delay::new(Duration::from_millis(down_delay)).fuse();
-
-                if let Some(next_shape) = shape.down().filter(|s| validate(&board, &s.body)) {
-                    shape = next_shape;
-                } else {
-                    change(&mut board, &shape, true);
-
-                    shape = random_shape();
-                    if !validate(&board, &shape.body) {
-                        change(&mut board, &shape, true);
-                        break;
-                    }
+            _ = next_frame => {
+                next_frame = Delay::new(frame).fuse();
+                if paused {
+                    continue;
+                }
+
+                let (num, den) = SPEEDS[speed];
+                let elapsed = anchor.0.elapsed().as_millis() as u64;
+                let due = anchor.1 + elapsed * FRAME_RATE as u64 * num / (1000 * den);
+                while playback.state().frame() < due && !playback.is_finished() {
+                    events.extend(playback.step());
                 }
             },
             event = next_event => {
-                if let Some(next_shape) = match event {
-                    Some(Ok(Event::Key(KeyEvent { code: KeyCode::Left, ..}))) => shape.left(),
-                    Some(Ok(Event::Key(KeyEvent { code: KeyCode::Right, ..}))) => shape.right(),
-                    Some(Ok(Event::Key(KeyEvent { code: KeyCode::Up, ..}))) => shape.turn_left(),
-                    Some(Ok(Event::Key(KeyEvent { code: KeyCode::Down, ..}))) => move_shape_down(shape.clone(), &board, Some(3)),
-                    Some(Ok(Event::Key(KeyEvent { code: KeyCode::Char(' '), ..}))) => move_shape_down(shape.clone(), &board, None),
-                    Some(Ok(Event::Key(KeyEvent { code: KeyCode::Esc, ..}))) => break,
-                    _ => None,
-
-                }.filter(|s| validate(&board, &s.body)) {
-                    shape = next_shape;
+                let key = match event {
+                    Some(Ok(Event::Key(key))) if key.kind == KeyEventKind::Press => key,
+                    Some(Ok(Event::Resize(columns, rows))) => {
+                        size = (columns, rows);
+                        stdout().execute(Clear(ClearType::All)).unwrap();
+                        continue;
+                    }
+                    _ => continue,
+                };
+                let now = playback.state().frame();
+                // Quit and pause are the keys bound for the game, the rest are fixed.
+                match (options.keys.action(&key), key.code) {
+                    (Some(Action::Quit), _) => break,
+                    (Some(Action::Pause), _) => paused = !paused,
+                    (_, KeyCode::Char('.')) => {
+                        paused = true;
+                        events = playback.step();
+                    }
+                    (_, KeyCode::Char('+')) => speed = (speed + 1).min(SPEEDS.len() - 1),
+                    (_, KeyCode::Char('-')) => speed = speed.saturating_sub(1),
+                    (_, KeyCode::Left) => playback.seek_frame(now.saturating_sub(SEEK_STEP)),
+                    (_, KeyCode::Right) => playback.seek_frame(now + SEEK_STEP),
+                    (_, KeyCode::Char(c)) if c.is_ascii_digit() && target.len() < 6 => {
+                        target.push(c)
+                    }
+                    (_, KeyCode::Backspace) => {
+                        target.pop();
+                    }
+                    (_, KeyCode::Char('t' | 'T')) => {
+                        let seconds = target.parse().unwrap_or(0);
+                        playback.seek_frame(seconds * FRAME_RATE as u64);
+                        target.clear();
+                    }
+                    (_, KeyCode::Char('n' | 'N')) => {
+                        playback.seek_piece(target.parse().unwrap_or(1));
+                        target.clear();
+                    }
+                    _ => continue,
                 }
+                // A label from later in the game would otherwise stay up after seeking back.
+                if playback.state().frame() < now {
+                    action = None;
+                }
+                anchor = (Instant::now(), playback.state().frame());
             },
         };
 
-        let new_score = remove_completed_lines(&mut board);
-        if new_score > 0 {
-            score += new_score;
-            print_board(&board, score, down_delay);
+        if let Some(label) = action_label(&events) {
+            action = Some((label, playback.state().frame() + 2 * FRAME_RATE as u64));
         }
     }
 
+    let row = Layout::new(&playback.replay().rules, glyphs, PLAYBACK_ROWS, size)
+        .map_or(size.1, |layout| layout.footer() + PLAYBACK_ROWS - 1);
+    stdout().queue(cursor::MoveTo(0, row)).unwrap();
     Ok(())
 }
 
-fn print_help() {
+/// The folder for replays and high scores in the user's data directory.
+fn data_dir() -> std::result::Result<PathBuf, String> {
+    Ok(dirs::data_dir().ok_or("no data directory")?.join("tetris"))
+}
+
+fn high_scores_path() -> std::result::Result<PathBuf, String> {
+    Ok(data_dir()?.join("highscores"))
+}
+
+/// Reads the high scores, there are none yet when the file does not exist.
+fn load_high_scores() -> std::result::Result<HighScores, String> {
+    let path = high_scores_path()?;
+    match fs::read_to_string(&path) {
+        Ok(text) => HighScores::parse(&text).map_err(|e| format!("{}: {}", path.display(), e)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(HighScores::default()),
+        Err(e) => Err(format!("{}: {}", path.display(), e)),
+    }
+}
+
+fn save_high_scores(high_scores: &HighScores) -> std::result::Result<(), String> {
+    let path = high_scores_path()?;
+    fs::create_dir_all(data_dir()?).map_err(|e| format!("{}: {}", path.display(), e))?;
+    fs::write(&path, high_scores.to_string()).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Writes the replay into the replays folder of the user's data directory.
+fn save_replay(replay: &Replay) -> std::result::Result<PathBuf, String> {
+    let dir = data_dir()?.join("replays");
+    fs::create_dir_all(&dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let path = dir.join(format!("{}-{}.replay", time, replay.seed));
+    fs::write(&path, replay.to_string()).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+fn print_key_help(keys: &Keymap, theme: &Theme, glyphs: &Glyphs) {
     let mut stdout = stdout();
     stdout.queue(cursor::MoveTo(0, 0)).unwrap();
     stdout.queue(Clear(ClearType::All)).unwrap();
@@ -254,101 +892,327 @@ fn print_help() {
         .unwrap();
 
     stdout
-        .queue(style::PrintStyledContent("Key bindings:".dark_blue()))
-        .unwrap();
-    stdout.queue(cursor::MoveTo(0, 1)).unwrap();
-    stdout
-        .queue(style::PrintStyledContent("\u{2190}".dark_red()))
-        .unwrap();
-    stdout
-        .queue(style::PrintStyledContent(" - ".dark_yellow()))
-        .unwrap();
-    stdout
-        .queue(style::PrintStyledContent("Move to the left".dark_green()))
-        .unwrap();
-    stdout.queue(cursor::MoveTo(0, 2)).unwrap();
-    stdout
-        .queue(style::PrintStyledContent("\u{2192}".dark_red()))
-        .unwrap();
-    stdout
-        .queue(style::PrintStyledContent(" - ".dark_yellow()))
-        .unwrap();
-    stdout
-        .queue(style::PrintStyledContent("Move to the right".dark_green()))
-        .unwrap();
-    stdout.queue(cursor::MoveTo(0, 3)).unwrap();
-    stdout
-        .queue(style::PrintStyledContent("\u{2191}".dark_red()))
-        .unwrap();
-    stdout
-        .queue(style::PrintStyledContent(" - ".dark_yellow()))
-        .unwrap();
-    stdout
-        .queue(style::PrintStyledContent("Rotate 90°".dark_green()))
-        .unwrap();
-    stdout.queue(cursor::MoveTo(0, 4)).unwrap();
-    stdout
-        .queue(style::PrintStyledContent("\u{2193}".dark_red()))
+        .queue(style::PrintStyledContent("Key bindings:".with(theme.text)))
         .unwrap();
+    for (i, action) in Action::ALL.into_iter().enumerate() {
+        let names = keys
+            .keys(action)
+            .iter()
+            .map(|key| key.label(glyphs))
+            .collect::<Vec<_>>();
+        stdout.queue(cursor::MoveTo(0, i as u16 + 1)).unwrap();
+        stdout
+            .queue(style::PrintStyledContent(
+                names.join(", ").with(theme.value),
+            ))
+            .unwrap();
+        stdout
+            .queue(style::PrintStyledContent(" - ".with(theme.action)))
+            .unwrap();
+        stdout
+            .queue(style::PrintStyledContent(
+                action
+                    .description()
+                    .replace('°', glyphs.degree)
+                    .with(theme.text),
+            ))
+            .unwrap();
+    }
+
     stdout
-        .queue(style::PrintStyledContent(" - ".dark_yellow()))
+        .queue(cursor::MoveTo(0, Action::ALL.len() as u16 + 3))
         .unwrap();
     stdout
-        .queue(style::PrintStyledContent("Move down 3 lines".dark_green()))
+        .queue(style::PrintStyledContent(
+            format!(
+                "Press {} for the high scores or any other key to start the game",
+                keys.keys(Action::HighScores)[0].label(glyphs)
+            )
+            .with(theme.text),
+        ))
         .unwrap();
-    stdout.queue(cursor::MoveTo(0, 5)).unwrap();
+}
+
+/// Shows the best scores of `mode` until a key is pressed.
+fn print_high_scores(high_scores: &HighScores, mode: &str, theme: &Theme) {
+    let mut stdout = stdout();
+    stdout.queue(cursor::MoveTo(0, 0)).unwrap();
+    stdout.queue(Clear(ClearType::All)).unwrap();
     stdout
-        .queue(style::PrintStyledContent("SPACE".dark_red()))
+        .queue(style::PrintStyledContent(
+            format!("High scores: {}", mode).with(theme.text),
+        ))
         .unwrap();
+
+    let table = high_scores.table(mode);
+    if table.is_empty() {
+        stdout.queue(cursor::MoveTo(0, 2)).unwrap();
+        stdout
+            .queue(style::PrintStyledContent("No scores yet".with(theme.text)))
+            .unwrap();
+    }
+    for (i, entry) in table.iter().enumerate() {
+        stdout.queue(cursor::MoveTo(0, i as u16 + 2)).unwrap();
+        stdout
+            .queue(style::PrintStyledContent(
+                format!("{:>2}. ", i + 1).with(theme.value),
+            ))
+            .unwrap();
+        stdout
+            .queue(style::PrintStyledContent(
+                format!(
+                    "{:<width$} {:>8}  Lines: {:<4} Level: {}",
+                    entry.name,
+                    entry.score,
+                    entry.lines,
+                    entry.level,
+                    width = MAX_NAME_LENGTH
+                )
+                .with(theme.text),
+            ))
+            .unwrap();
+    }
+
     stdout
-        .queue(style::PrintStyledContent(" - ".dark_yellow()))
+        .queue(cursor::MoveTo(0, table.len().max(1) as u16 + 3))
         .unwrap();
     stdout
-        .queue(style::PrintStyledContent("drop down".dark_green()))
+        .queue(style::PrintStyledContent(
+            "Press any key to go back".with(theme.text),
+        ))
         .unwrap();
-    stdout.queue(cursor::MoveTo(0, 6)).unwrap();
+    stdout.flush().unwrap();
+
+    while !matches!(
+        read().unwrap(),
+        Event::Key(KeyEvent {
+            kind: KeyEventKind::Press,
+            ..
+        })
+    ) {}
+}
+
+fn print_help(options: &Options, high_scores: &HighScores) -> u32 {
+    let keys = &options.keys;
+    let theme = &options.theme;
+    let glyphs = options.glyphs;
+    let mut stdout = stdout();
+    print_key_help(keys, theme, glyphs);
+
+    let mut level = options.rules.start_level;
+    loop {
+        stdout
+            .queue(cursor::MoveTo(0, Action::ALL.len() as u16 + 2))
+            .unwrap();
+        stdout
+            .queue(style::PrintStyledContent(
+                "Starting level: ".with(theme.text),
+            ))
+            .unwrap();
+        stdout
+            .queue(style::PrintStyledContent(
+                format!(
+                    "{} {:>2} {}",
+                    keys.keys(Action::Left)[0].label(glyphs),
+                    level,
+                    keys.keys(Action::Right)[0].label(glyphs)
+                )
+                .with(theme.value),
+            ))
+            .unwrap();
+        stdout.flush().unwrap();
+
+        let Event::Key(key) = read().unwrap() else {
+            continue;
+        };
+        if key.kind == KeyEventKind::Release {
+            continue;
+        }
+        match keys.start_action(&key) {
+            Some(Action::Left) => level = (level - 1).max(1),
+            Some(Action::Right) => level = (level + 1).min(MAX_START_LEVEL),
+            Some(Action::HighScores) => {
+                // The starting level is part of the mode.
+                let rules = Rules {
+                    start_level: level,
+                    ..options.rules.clone()
+                };
+                print_high_scores(high_scores, &rules.mode(), theme);
+                print_key_help(keys, theme, glyphs);
+            }
+            _ => break,
+        }
+    }
+
     stdout
-        .queue(style::PrintStyledContent("ESC".dark_red()))
+        .queue(style::SetAttribute(style::Attribute::Reset))
         .unwrap();
+
+    stdout.execute(Clear(ClearType::All)).unwrap();
+
+    level
+}
+
+/// Rows of GAME OVER, the seed, the replay path, the name entry and the high score place.
+const GAME_OVER_ROWS: u16 = 5;
+
+/// Where the game over lines go, under the game when they fit there and otherwise at the
+/// top of a cleared screen.
+fn game_over_position(options: &Options) -> (u16, u16) {
+    let size = terminal::size().unwrap_or_default();
+    match Layout::new(&options.rules, options.glyphs, 0, size) {
+        Ok(layout) if layout.footer() + GAME_OVER_ROWS <= size.1 => (layout.left, layout.footer()),
+        _ => {
+            stdout().execute(Clear(ClearType::All)).unwrap();
+            (0, 0)
+        }
+    }
+}
+
+fn print_game_over(
+    options: &Options,
+    game: Result<(GameState, Replay)>,
+    high_scores: Option<&mut HighScores>,
+) {
+    let theme = &options.theme;
+    let (col, row) = game_over_position(options);
+    let mut stdout = stdout();
+    stdout.queue(cursor::MoveTo(col, row)).unwrap();
     stdout
-        .queue(style::PrintStyledContent(" - ".dark_yellow()))
+        .queue(style::SetAttribute(style::Attribute::Bold))
         .unwrap();
     stdout
-        .queue(style::PrintStyledContent("quit the game".dark_green()))
+        .queue(style::PrintStyledContent("GAME OVER".with(theme.value)))
         .unwrap();
-    stdout.queue(cursor::MoveTo(0, 8)).unwrap();
+    stdout.queue(cursor::MoveTo(col, row + 1)).unwrap();
     stdout
         .queue(style::PrintStyledContent(
-            "Press any key to start the game".dark_blue(),
+            format!("Seed: {}", options.seed).with(theme.text),
         ))
         .unwrap();
+    let Ok((state, replay)) = game else {
+        return;
+    };
 
+    let saved = match save_replay(&replay) {
+        Ok(path) => format!("Replay: {}", path.display()),
+        Err(e) => format!("Replay not saved: {}", e),
+    };
+    stdout.queue(cursor::MoveTo(col, row + 2)).unwrap();
     stdout
-        .queue(style::SetAttribute(style::Attribute::Reset))
+        .queue(style::PrintStyledContent(saved.with(theme.text)))
         .unwrap();
 
-    stdout.flush().unwrap();
+    let mode = options.rules.mode();
+    let Some(high_scores) = high_scores.filter(|h| h.qualifies(&mode, state.score())) else {
+        return;
+    };
+    let name = read_name(theme, col, row + 3);
+    let entry = Entry {
+        name,
+        score: state.score(),
+        lines: state.lines(),
+        level: state.level(),
+    };
+    let message = match high_scores.insert(&mode, entry) {
+        Some(place) => match save_high_scores(high_scores) {
+            Ok(()) => format!("Place {} in the {} high scores", place, mode),
+            Err(e) => format!("High score not saved: {}", e),
+        },
+        None => String::new(),
+    };
+    stdout.queue(cursor::MoveTo(col, row + 4)).unwrap();
+    stdout
+        .queue(style::PrintStyledContent(message.with(theme.text)))
+        .unwrap();
+}
 
-    read().unwrap();
+/// Asks for the name of a new high score at `col` and `row`, confirmed with Enter.
+fn read_name(theme: &Theme, col: u16, row: u16) -> String {
+    let mut stdout = stdout();
+    let mut name = String::new();
+    loop {
+        stdout.queue(cursor::MoveTo(col, row)).unwrap();
+        stdout.queue(Clear(ClearType::UntilNewLine)).unwrap();
+        stdout
+            .queue(style::PrintStyledContent(
+                format!("NEW HIGH SCORE! Name: {}_", name).with(theme.action),
+            ))
+            .unwrap();
+        stdout.flush().unwrap();
 
-    stdout.execute(Clear(ClearType::All)).unwrap();
+        let Event::Key(KeyEvent {
+            code,
+            kind: KeyEventKind::Press,
+            ..
+        }) = read().unwrap()
+        else {
+            continue;
+        };
+        match code {
+            KeyCode::Enter => return name,
+            KeyCode::Backspace => {
+                name.pop();
+            }
+            KeyCode::Char(c) if name.chars().count() < MAX_NAME_LENGTH => name.push(c),
+            _ => (),
+        }
+    }
 }
 
 fn main() {
+    let mut options = parse_args().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        eprintln!(
+            "usage: tetris [--seed NUMBER] [--randomizer random|7bag|14bag|tgm] [--sequence FILE] \
+             [--width COLUMNS] [--height ROWS] [--no-hold] [--preview 0-7] [--no-ghost] \
+             [--level 1-15] [--lock-delay MS] [--lock-reset infinite|move|step] \
+             [--gravity guideline|nes|tgm|20g] [--das MS] [--arr MS] \
+             [--soft-drop-factor 1-100] [--line-clear-delay MS] [--are MS] [--replay FILE] \
+             [--config FILE] [--ascii]"
+        );
+        process::exit(2);
+    });
+
+    // High scores are left alone when their file cannot be read, rather than overwritten.
+    let mut high_scores = load_high_scores()
+        .map_err(|e| eprintln!("High scores disabled: {}", e))
+        .ok();
+
     enable_raw_mode().unwrap();
     let mut stdout = stdout();
     stdout.queue(cursor::Hide).unwrap();
-    print_help();
 
-    let _ = executor::block_on(run_game());
+    stdout.execute(EnableFocusChange).unwrap();
+    // Key releases drive auto-repeat, terminals which do not report them fall back to
+    // their own key repeat.
+    let key_releases = cfg!(windows) || supports_keyboard_enhancement().unwrap_or(false);
+    if key_releases && !cfg!(windows) {
+        stdout
+            .execute(PushKeyboardEnhancementFlags(
+                KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
+                    | KeyboardEnhancementFlags::REPORT_EVENT_TYPES,
+            ))
+            .unwrap();
+    }
 
-    stdout.queue(cursor::MoveTo(4, 25)).unwrap();
-    stdout
-        .queue(style::SetAttribute(style::Attribute::Bold))
-        .unwrap();
-    stdout
-        .queue(style::PrintStyledContent("GAME OVER\n\n".dark_red()))
-        .unwrap();
+    if let Some(replay) = options.replay.take() {
+        let _ = executor::block_on(run_playback(replay, &options));
+    } else {
+        options.rules.start_level = print_help(
+            &options,
+            high_scores.as_ref().unwrap_or(&HighScores::default()),
+        );
+        let game = executor::block_on(run_game(&options, key_releases));
+        print_game_over(&options, game, high_scores.as_mut());
+    }
+
+    if key_releases && !cfg!(windows) {
+        stdout.execute(PopKeyboardEnhancementFlags).unwrap();
+    }
+    stdout.execute(DisableFocusChange).unwrap();
+
+    stdout.queue(style::Print("\n\n")).unwrap();
     stdout
         .queue(style::SetAttribute(style::Attribute::Reset))
         .unwrap();
@@ -0,0 +1,240 @@
+use crate::rng::Rng;
+use crate::shapes::ShapeType;
+
+pub trait Randomizer {
+    fn next(&mut self, rng: &mut Rng) -> ShapeType;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RandomizerKind {
+    Random,
+    Bag7,
+    Bag14,
+    History,
+    Fixed(Vec<ShapeType>),
+}
+
+impl RandomizerKind {
+    /// Looks up a randomizer by the name `name` returns.
+    pub fn from_name(name: &str) -> Option<RandomizerKind> {
+        match name {
+            "random" => Some(RandomizerKind::Random),
+            "7bag" => Some(RandomizerKind::Bag7),
+            "14bag" => Some(RandomizerKind::Bag14),
+            "tgm" => Some(RandomizerKind::History),
+            _ => None,
+        }
+    }
+
+    /// The name of the randomizer on the command line, fixed sequences have none.
+    pub fn name(&self) -> Option<&'static str> {
+        match self {
+            RandomizerKind::Random => Some("random"),
+            RandomizerKind::Bag7 => Some("7bag"),
+            RandomizerKind::Bag14 => Some("14bag"),
+            RandomizerKind::History => Some("tgm"),
+            RandomizerKind::Fixed(_) => None,
+        }
+    }
+
+    pub fn build(&self) -> Box<dyn Randomizer> {
+        match self {
+            RandomizerKind::Random => Box::new(PureRandom),
+            RandomizerKind::Bag7 => Box::new(Bag::new(1)),
+            RandomizerKind::Bag14 => Box::new(Bag::new(2)),
+            RandomizerKind::History => Box::new(History::new()),
+            RandomizerKind::Fixed(sequence) => Box::new(FixedSequence::new(sequence.clone())),
+        }
+    }
+}
+
+/// Every shape type is equally likely on every draw.
+pub struct PureRandom;
+
+impl Randomizer for PureRandom {
+    fn next(&mut self, rng: &mut Rng) -> ShapeType {
+        ShapeType::ALL[rng.below(7) as usize]
+    }
+}
+
+/// Deals shuffled bags holding `copies` of each of the seven shape types.
+pub struct Bag {
+    copies: usize,
+    bag: Vec<ShapeType>,
+}
+
+impl Bag {
+    pub fn new(copies: usize) -> Self {
+        Self {
+            copies,
+            bag: Vec::with_capacity(copies * 7),
+        }
+    }
+}
+
+impl Randomizer for Bag {
+    fn next(&mut self, rng: &mut Rng) -> ShapeType {
+        if self.bag.is_empty() {
+            for _ in 0..self.copies {
+                self.bag.extend_from_slice(&ShapeType::ALL);
+            }
+            for i in (1..self.bag.len()).rev() {
+                let j = rng.below(i as u32 + 1) as usize;
+                self.bag.swap(i, j);
+            }
+        }
+
+        self.bag.pop().unwrap()
+    }
+}
+
+/// TGM style randomizer: rerolls up to four times while the draw is one of the last four
+/// shapes, and never starts with an S, Z or O.
+pub struct History {
+    history: [ShapeType; 4],
+    first: bool,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self {
+            history: [ShapeType::SMirrored; 4],
+            first: true,
+        }
+    }
+}
+
+impl Randomizer for History {
+    fn next(&mut self, rng: &mut Rng) -> ShapeType {
+        let shape_type = if self.first {
+            self.first = false;
+            [
+                ShapeType::Line,
+                ShapeType::LMirrored,
+                ShapeType::LForm,
+                ShapeType::TForm,
+            ][rng.below(4) as usize]
+        } else {
+            let mut shape_type = ShapeType::ALL[rng.below(7) as usize];
+            for _ in 0..4 {
+                if !self.history.contains(&shape_type) {
+                    break;
+                }
+                shape_type = ShapeType::ALL[rng.below(7) as usize];
+            }
+            shape_type
+        };
+
+        self.history.rotate_left(1);
+        self.history[3] = shape_type;
+        shape_type
+    }
+}
+
+/// Repeats a predefined sequence of shapes.
+pub struct FixedSequence {
+    sequence: Vec<ShapeType>,
+    index: usize,
+}
+
+impl FixedSequence {
+    pub fn new(sequence: Vec<ShapeType>) -> Self {
+        assert!(!sequence.is_empty(), "empty shape sequence");
+        Self { sequence, index: 0 }
+    }
+
+    /// Parses a sequence of piece letters such as `"IOTSZJL"`, ignoring whitespace.
+    pub fn parse(text: &str) -> Result<Vec<ShapeType>, String> {
+        let sequence = text
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| ShapeType::from_letter(c).ok_or(format!("unknown piece '{}'", c)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if sequence.is_empty() {
+            return Err("the sequence is empty".to_string());
+        }
+
+        Ok(sequence)
+    }
+}
+
+impl Randomizer for FixedSequence {
+    fn next(&mut self, _rng: &mut Rng) -> ShapeType {
+        let shape_type = self.sequence[self.index];
+        self.index = (self.index + 1) % self.sequence.len();
+        shape_type
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn count(shapes: &[ShapeType], shape_type: ShapeType) -> usize {
+        shapes.iter().filter(|t| **t == shape_type).count()
+    }
+
+    #[test]
+    fn bag_test() {
+        let mut rng = Rng::new(3);
+        let mut bag = Bag::new(1);
+        for _ in 0..10 {
+            let shapes = (0..7).map(|_| bag.next(&mut rng)).collect::<Vec<_>>();
+            for t in ShapeType::ALL {
+                assert_eq!(count(&shapes, t), 1);
+            }
+        }
+
+        let mut bag = Bag::new(2);
+        for _ in 0..10 {
+            let shapes = (0..14).map(|_| bag.next(&mut rng)).collect::<Vec<_>>();
+            for t in ShapeType::ALL {
+                assert_eq!(count(&shapes, t), 2);
+            }
+        }
+    }
+
+    #[test]
+    fn history_test() {
+        for seed in 0..50 {
+            let mut rng = Rng::new(seed);
+            let mut history = History::new();
+            let first = history.next(&mut rng);
+            assert!(![ShapeType::SForm, ShapeType::SMirrored, ShapeType::Quadrat].contains(&first));
+        }
+    }
+
+    #[test]
+    fn fixed_sequence_test() {
+        let sequence = FixedSequence::parse("io t\nSz").unwrap();
+        assert_eq!(
+            sequence,
+            vec![
+                ShapeType::Line,
+                ShapeType::Quadrat,
+                ShapeType::TForm,
+                ShapeType::SForm,
+                ShapeType::SMirrored
+            ]
+        );
+
+        let mut rng = Rng::new(0);
+        let mut fixed = FixedSequence::new(sequence.clone());
+        let shapes = (0..10).map(|_| fixed.next(&mut rng)).collect::<Vec<_>>();
+        assert_eq!(&shapes[..5], &sequence[..]);
+        assert_eq!(&shapes[5..], &sequence[..]);
+
+        assert_eq!(
+            FixedSequence::parse("IOX"),
+            Err("unknown piece 'X'".to_string())
+        );
+        assert!(FixedSequence::parse(" \n").is_err());
+    }
+}
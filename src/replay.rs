@@ -0,0 +1,395 @@
+use crate::game::{GameEvent, GameState, Input};
+use crate::gravity::GravityCurve;
+use crate::handling::{Controller, Handling, InputEvent};
+use crate::randomizer::{FixedSequence, RandomizerKind};
+use crate::rules::{LockReset, Rules};
+use std::fmt;
+
+/// The first line of every replay, followed by the format version.
+const MAGIC: &str = "tetris-replay";
+const VERSION: u32 = 1;
+
+/// Inputs written on each line of the input log.
+const INPUTS_PER_LINE: usize = 16;
+
+/// Everything needed to play a game again: the seed and settings it started with, and the
+/// key presses and releases stamped with the frame they happened before.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replay {
+    pub seed: u64,
+    pub rules: Rules,
+    pub handling: Handling,
+    pub inputs: Vec<(u64, InputEvent)>,
+    /// Number of frames the game lasted.
+    pub frames: u64,
+    /// The final score, which playing the replay back has to reach again.
+    pub score: u32,
+}
+
+impl Replay {
+    pub fn new(seed: u64, rules: Rules, handling: Handling) -> Self {
+        Self {
+            seed,
+            rules,
+            handling,
+            inputs: Vec::new(),
+            frames: 0,
+            score: 0,
+        }
+    }
+
+    pub fn record(&mut self, frame: u64, event: InputEvent) {
+        self.inputs.push((frame, event));
+    }
+
+    /// Reads a replay in the format `Display` writes.
+    pub fn parse(text: &str) -> Result<Replay, String> {
+        let mut lines = text.lines();
+        let version = lines
+            .next()
+            .and_then(|line| line.strip_prefix(MAGIC))
+            .ok_or("not a replay file")?;
+        if version.trim() != VERSION.to_string() {
+            return Err(format!("unsupported replay version {}", version.trim()));
+        }
+
+        let mut replay = Replay::new(0, Rules::default(), Handling::default());
+        for line in lines.by_ref() {
+            if line == "inputs" {
+                break;
+            }
+
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            let number = || {
+                value
+                    .parse::<u32>()
+                    .map_err(|_| format!("invalid {}: {}", key, value))
+            };
+            let rules = &mut replay.rules;
+            match key {
+                "seed" => {
+                    replay.seed = value
+                        .parse()
+                        .map_err(|_| format!("invalid seed: {}", value))?
+                }
+                "frames" => {
+                    replay.frames = value
+                        .parse()
+                        .map_err(|_| format!("invalid frames: {}", value))?
+                }
+                "score" => replay.score = number()?,
+                "randomizer" => {
+                    rules.randomizer = RandomizerKind::from_name(value)
+                        .ok_or_else(|| format!("unknown randomizer: {}", value))?
+                }
+                "sequence" => {
+                    rules.randomizer = RandomizerKind::Fixed(FixedSequence::parse(value)?)
+                }
+                "width" => rules.width = number()? as usize,
+                "height" => rules.height = number()? as usize,
+                "hold" => rules.hold = number()? != 0,
+                "preview" => rules.preview = number()? as usize,
+                "lock-delay" => rules.lock_delay = number()?,
+                "lock-reset" => {
+                    rules.lock_reset = LockReset::from_name(value)
+                        .ok_or_else(|| format!("unknown lock reset: {}", value))?
+                }
+                "level" => rules.start_level = number()?,
+                "lines-per-level" => rules.lines_per_level = number()?,
+                "gravity" => {
+                    rules.gravity = GravityCurve::from_name(value)
+                        .ok_or_else(|| format!("unknown gravity: {}", value))?
+                }
+                "line-clear-delay" => rules.line_clear_delay = number()?,
+                "are" => rules.entry_delay = number()?,
+                "das" => replay.handling.das = number()?,
+                "arr" => replay.handling.arr = number()?,
+                "soft-drop-factor" => replay.handling.soft_drop_factor = number()?,
+                _ => return Err(format!("unknown replay setting: {}", line)),
+            }
+        }
+        replay.rules.validate()?;
+        replay.handling.validate()?;
+
+        let mut frame: u64 = 0;
+        for entry in lines.flat_map(|line| line.split_whitespace()) {
+            let key = entry.chars().last().unwrap();
+            let delta = &entry[..entry.len() - key.len_utf8()];
+            frame = delta
+                .parse::<u64>()
+                .ok()
+                .and_then(|delta| frame.checked_add(delta))
+                .ok_or_else(|| format!("invalid input: {}", entry))?;
+            let input = input_from_letter(key.to_ascii_uppercase())
+                .ok_or_else(|| format!("invalid input: {}", entry))?;
+            let event = match key.is_ascii_uppercase() {
+                true => InputEvent::Press(input),
+                false => InputEvent::Release(input),
+            };
+            replay.record(frame, event);
+        }
+
+        Ok(replay)
+    }
+}
+
+/// Writes the settings one per line, then the input log as frames since the previous input
+/// and a letter for the key, upper case for presses and lower case for releases.
+impl fmt::Display for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let rules = &self.rules;
+        writeln!(f, "{} {}", MAGIC, VERSION)?;
+        writeln!(f, "seed {}", self.seed)?;
+        writeln!(f, "frames {}", self.frames)?;
+        writeln!(f, "score {}", self.score)?;
+        match &rules.randomizer {
+            RandomizerKind::Fixed(sequence) => writeln!(
+                f,
+                "sequence {}",
+                sequence.iter().map(|t| t.letter()).collect::<String>()
+            )?,
+            randomizer => writeln!(f, "randomizer {}", randomizer.name().unwrap())?,
+        }
+        writeln!(f, "width {}", rules.width)?;
+        writeln!(f, "height {}", rules.height)?;
+        writeln!(f, "hold {}", rules.hold as u32)?;
+        writeln!(f, "preview {}", rules.preview)?;
+        writeln!(f, "lock-delay {}", rules.lock_delay)?;
+        writeln!(f, "lock-reset {}", rules.lock_reset.name())?;
+        writeln!(f, "level {}", rules.start_level)?;
+        writeln!(f, "lines-per-level {}", rules.lines_per_level)?;
+        writeln!(f, "gravity {}", rules.gravity.name())?;
+        writeln!(f, "line-clear-delay {}", rules.line_clear_delay)?;
+        writeln!(f, "are {}", rules.entry_delay)?;
+        writeln!(f, "das {}", self.handling.das)?;
+        writeln!(f, "arr {}", self.handling.arr)?;
+        writeln!(f, "soft-drop-factor {}", self.handling.soft_drop_factor)?;
+        writeln!(f, "inputs")?;
+
+        let mut last = 0;
+        for chunk in self.inputs.chunks(INPUTS_PER_LINE) {
+            let entries = chunk
+                .iter()
+                .map(|(frame, event)| {
+                    let delta = frame - last;
+                    last = *frame;
+                    match event {
+                        InputEvent::Press(input) => format!("{}{}", delta, letter(*input)),
+                        InputEvent::Release(input) => {
+                            format!("{}{}", delta, letter(*input).to_ascii_lowercase())
+                        }
+                    }
+                })
+                .collect::<Vec<_>>();
+            writeln!(f, "{}", entries.join(" "))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Plays a replay back by feeding its inputs to a new game, one frame at a time.
+pub struct Playback {
+    replay: Replay,
+    state: GameState,
+    controller: Controller,
+    /// Index of the next input to hand to the game.
+    next_input: usize,
+    pieces: u32,
+}
+
+impl Playback {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            state: GameState::new(replay.seed, replay.rules.clone()),
+            controller: Controller::new(replay.handling),
+            replay,
+            next_input: 0,
+            pieces: 0,
+        }
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    pub fn state(&self) -> &GameState {
+        &self.state
+    }
+
+    /// Number of pieces locked so far.
+    pub fn pieces(&self) -> u32 {
+        self.pieces
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.state.is_game_over()
+            || (self.state.frame() >= self.replay.frames
+                && self.next_input == self.replay.inputs.len())
+    }
+
+    /// Whether the finished game differs from the recorded one, which means the engine did
+    /// not play the inputs back the same way.
+    pub fn diverged(&self) -> bool {
+        self.is_finished() && self.state.score() != self.replay.score
+    }
+
+    /// Hands the game the inputs of the current frame and plays it.
+    pub fn step(&mut self) -> Vec<GameEvent> {
+        let mut events = Vec::new();
+        while let Some(&(frame, event)) = self.replay.inputs.get(self.next_input) {
+            if frame > self.state.frame() {
+                break;
+            }
+            events.extend(self.controller.handle(&mut self.state, event));
+            self.next_input += 1;
+        }
+        if !self.is_finished() {
+            events.extend(self.controller.tick(&mut self.state));
+        }
+
+        let locked = events.iter().filter(|e| **e == GameEvent::PieceLocked);
+        self.pieces += locked.count() as u32;
+        events
+    }
+
+    /// Plays from the start up to `frame`, or the end of the replay.
+    pub fn seek_frame(&mut self, frame: u64) {
+        if frame < self.state.frame() {
+            self.restart();
+        }
+        while self.state.frame() < frame && !self.is_finished() {
+            self.step();
+        }
+    }
+
+    /// Plays from the start until the piece numbered `piece`, counting from 1, enters.
+    pub fn seek_piece(&mut self, piece: u32) {
+        let locked = piece.max(1) - 1;
+        if locked < self.pieces {
+            self.restart();
+        }
+        while self.pieces < locked && !self.is_finished() {
+            self.step();
+        }
+    }
+
+    fn restart(&mut self) {
+        *self = Playback::new(self.replay.clone());
+    }
+}
+
+fn letter(input: Input) -> char {
+    match input {
+        Input::Left => 'L',
+        Input::Right => 'R',
+        Input::RotateLeft => 'Z',
+        Input::RotateRight => 'X',
+        Input::Rotate180 => 'A',
+        Input::SoftDrop => 'S',
+        Input::HardDrop => 'H',
+        Input::Hold => 'C',
+    }
+}
+
+fn input_from_letter(c: char) -> Option<Input> {
+    match c {
+        'L' => Some(Input::Left),
+        'R' => Some(Input::Right),
+        'Z' => Some(Input::RotateLeft),
+        'X' => Some(Input::RotateRight),
+        'A' => Some(Input::Rotate180),
+        'S' => Some(Input::SoftDrop),
+        'H' => Some(Input::HardDrop),
+        'C' => Some(Input::Hold),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::shapes::ShapeType;
+
+    fn replay() -> Replay {
+        let rules = Rules {
+            randomizer: RandomizerKind::Fixed(vec![ShapeType::Line, ShapeType::TForm]),
+            gravity: GravityCurve::Nes,
+            start_level: 3,
+            hold: false,
+            ..Rules::default()
+        };
+        let mut replay = Replay::new(42, rules, Handling::default());
+        replay.record(0, InputEvent::Press(Input::Left));
+        replay.record(20, InputEvent::Release(Input::Left));
+        replay.record(20, InputEvent::Press(Input::HardDrop));
+        replay.record(21, InputEvent::Release(Input::HardDrop));
+        replay.record(30, InputEvent::Press(Input::RotateRight));
+        replay.record(90, InputEvent::Press(Input::SoftDrop));
+        replay.frames = 200;
+        replay.score = 74;
+        replay
+    }
+
+    #[test]
+    fn round_trip_test() {
+        let replay = replay();
+        let text = replay.to_string();
+        assert!(text.starts_with("tetris-replay 1\nseed 42\n"));
+        assert!(text.contains("sequence IT\n"));
+        assert!(text.ends_with("inputs\n0L 20l 0H 1h 9X 60S\n"));
+        assert_eq!(Replay::parse(&text), Ok(replay));
+    }
+
+    #[test]
+    fn parse_error_test() {
+        let text = replay().to_string();
+        assert_eq!(
+            Replay::parse("hello").unwrap_err(),
+            "not a replay file".to_string()
+        );
+        assert_eq!(
+            Replay::parse(&text.replace("replay 1", "replay 7")).unwrap_err(),
+            "unsupported replay version 7".to_string()
+        );
+        assert_eq!(
+            Replay::parse(&text.replace("gravity nes", "gravity fast")).unwrap_err(),
+            "unknown gravity: fast".to_string()
+        );
+        assert_eq!(
+            Replay::parse(&text.replace("9X", "9Q")).unwrap_err(),
+            "invalid input: 9Q".to_string()
+        );
+        assert_eq!(
+            Replay::parse(&text.replace("60S", "18446744073709551615L 1L")).unwrap_err(),
+            "invalid input: 18446744073709551615L".to_string()
+        );
+        assert!(Replay::parse(&text.replace("width 10", "width 2")).is_err());
+    }
+
+    #[test]
+    fn playback_test() {
+        let mut playback = Playback::new(Replay::parse(&replay().to_string()).unwrap());
+        while !playback.is_finished() {
+            playback.step();
+        }
+        assert_eq!(playback.state().frame(), 200);
+        assert_eq!(playback.pieces(), 2);
+        assert!(!playback.diverged());
+        let cells = playback.state().cells();
+
+        playback.seek_frame(25);
+        assert_eq!(playback.state().frame(), 25);
+        assert_eq!(playback.pieces(), 1);
+        playback.seek_piece(1);
+        assert_eq!(playback.state().frame(), 0);
+        playback.seek_frame(500);
+        assert_eq!(playback.state().cells(), cells);
+
+        let mut replay = replay();
+        replay.score += 1;
+        let mut playback = Playback::new(replay);
+        playback.seek_frame(200);
+        assert!(playback.diverged());
+    }
+}
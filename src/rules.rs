@@ -0,0 +1,176 @@
+use crate::gravity::GravityCurve;
+use crate::randomizer::RandomizerKind;
+
+pub const MAX_START_LEVEL: u32 = 15;
+
+/// What gives a shape resting on the ground more time before it locks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockReset {
+    /// Every move or turn restarts the lock delay.
+    Infinite,
+    /// Moves and turns restart the lock delay 15 times, until the shape reaches a new row.
+    Move,
+    /// Only moving down a row restarts the lock delay.
+    Step,
+}
+
+impl LockReset {
+    pub fn from_name(name: &str) -> Option<LockReset> {
+        match name {
+            "infinite" => Some(LockReset::Infinite),
+            "move" => Some(LockReset::Move),
+            "step" => Some(LockReset::Step),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LockReset::Infinite => "infinite",
+            LockReset::Move => "move",
+            LockReset::Step => "step",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rules {
+    pub randomizer: RandomizerKind,
+    pub width: usize,
+    pub height: usize,
+    pub hold: bool,
+    /// Number of upcoming shapes shown, at most 7.
+    pub preview: usize,
+    /// Milliseconds a shape rests on the ground before it locks.
+    pub lock_delay: u32,
+    pub lock_reset: LockReset,
+    pub start_level: u32,
+    /// Lines to clear for each level up.
+    pub lines_per_level: u32,
+    pub gravity: GravityCurve,
+    /// Milliseconds completed rows are shown before they are removed.
+    pub line_clear_delay: u32,
+    /// Milliseconds between a lock or line clear and the next shape entering (ARE).
+    pub entry_delay: u32,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            randomizer: RandomizerKind::Bag7,
+            width: 10,
+            height: 20,
+            hold: true,
+            preview: 5,
+            lock_delay: 500,
+            lock_reset: LockReset::Move,
+            start_level: 1,
+            lines_per_level: 10,
+            gravity: GravityCurve::Guideline,
+            line_clear_delay: 300,
+            entry_delay: 0,
+        }
+    }
+}
+
+impl Rules {
+    /// Names the settings which make scores comparable, such as "guideline-7bag-10x20",
+    /// followed by the other rules which differ from the defaults, such as "-level5-nohold".
+    pub fn mode(&self) -> String {
+        let default = Rules::default();
+        let mut mode = format!(
+            "{}-{}-{}x{}",
+            self.gravity.name(),
+            self.randomizer.name().unwrap_or("sequence"),
+            self.width,
+            self.height
+        );
+        if self.start_level != default.start_level {
+            mode.push_str(&format!("-level{}", self.start_level));
+        }
+        if self.lines_per_level != default.lines_per_level {
+            mode.push_str(&format!("-{}lines", self.lines_per_level));
+        }
+        if !self.hold {
+            mode.push_str("-nohold");
+        }
+        if self.preview != default.preview {
+            mode.push_str(&format!("-preview{}", self.preview));
+        }
+        if self.lock_delay != default.lock_delay || self.lock_reset != default.lock_reset {
+            mode.push_str(&format!(
+                "-lock{}{}",
+                self.lock_delay,
+                self.lock_reset.name()
+            ));
+        }
+        if self.line_clear_delay != default.line_clear_delay {
+            mode.push_str(&format!("-clear{}", self.line_clear_delay));
+        }
+        if self.entry_delay != default.entry_delay {
+            mode.push_str(&format!("-are{}", self.entry_delay));
+        }
+        mode
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !(4..=40).contains(&self.width) {
+            return Err(format!("board width {} is not within 4..=40", self.width));
+        }
+        if !(4..=60).contains(&self.height) {
+            return Err(format!("board height {} is not within 4..=60", self.height));
+        }
+        if self.preview > 7 {
+            return Err(format!("preview of {} shapes is more than 7", self.preview));
+        }
+        if self.lock_delay > 10_000 {
+            return Err(format!("lock delay of {} ms is too long", self.lock_delay));
+        }
+        if !(1..=MAX_START_LEVEL).contains(&self.start_level) {
+            return Err(format!(
+                "starting level {} is not within 1..={}",
+                self.start_level, MAX_START_LEVEL
+            ));
+        }
+        if self.line_clear_delay > 5_000 {
+            return Err(format!(
+                "line clear delay of {} ms is too long",
+                self.line_clear_delay
+            ));
+        }
+        if self.entry_delay > 5_000 {
+            return Err(format!(
+                "entry delay of {} ms is too long",
+                self.entry_delay
+            ));
+        }
+        if self.lines_per_level == 0 {
+            return Err("lines per level must be at least 1".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn mode_test() {
+        assert_eq!(Rules::default().mode(), "guideline-7bag-10x20");
+
+        let rules = Rules {
+            start_level: 15,
+            hold: false,
+            preview: 0,
+            lock_delay: 10_000,
+            lock_reset: LockReset::Infinite,
+            ..Rules::default()
+        };
+        assert_eq!(
+            rules.mode(),
+            "guideline-7bag-10x20-level15-nohold-preview0-lock10000infinite"
+        );
+    }
+}
@@ -0,0 +1,198 @@
+/// Guideline scoring: line clears scaled by level, drop points, combos, back-to-back
+/// and perfect clear bonuses.
+#[derive(Debug, Clone, Default)]
+pub struct Scoring {
+    score: u32,
+    lines: u32,
+    combo: Option<u32>,
+    back_to_back: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TSpin {
+    Mini,
+    Full,
+}
+
+/// What a lock earned beyond the plain line clear.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Award {
+    pub points: u32,
+    pub t_spin: Option<TSpin>,
+    pub combo: Option<u32>,
+    pub back_to_back: bool,
+    pub perfect_clear: bool,
+}
+
+impl Scoring {
+    pub fn score(&self) -> u32 {
+        self.score
+    }
+
+    pub fn lines(&self) -> u32 {
+        self.lines
+    }
+
+    /// The number of consecutive line clearing locks after the first one.
+    pub fn combo(&self) -> Option<u32> {
+        self.combo
+    }
+
+    pub fn soft_drop(&mut self, rows: u32) {
+        self.score += rows;
+    }
+
+    pub fn hard_drop(&mut self, rows: u32) {
+        self.score += 2 * rows;
+    }
+
+    /// Scores a locked shape which cleared `lines` rows at `level`.
+    pub fn lock(
+        &mut self,
+        lines: u32,
+        level: u32,
+        t_spin: Option<TSpin>,
+        perfect_clear: bool,
+    ) -> Award {
+        let mut points = match t_spin {
+            None => [0, 100, 300, 500, 800][lines as usize],
+            Some(TSpin::Mini) => [100, 200, 400, 400, 400][lines as usize],
+            Some(TSpin::Full) => [400, 800, 1200, 1600, 1600][lines as usize],
+        };
+
+        // A T-spin without lines scores but leaves the back-to-back chain alone.
+        if lines == 0 {
+            self.combo = None;
+            points *= level;
+            self.score += points;
+            return Award {
+                points,
+                t_spin,
+                ..Award::default()
+            };
+        }
+
+        let difficult = lines == 4 || t_spin.is_some();
+        let back_to_back = difficult && self.back_to_back;
+        self.back_to_back = difficult;
+
+        if back_to_back {
+            points = points * 3 / 2;
+        }
+
+        let combo = self.combo.map_or(0, |c| c + 1);
+        self.combo = Some(combo);
+        points += 50 * combo;
+
+        if perfect_clear {
+            points += match lines {
+                1 => 800,
+                2 => 1200,
+                3 => 1800,
+                _ if back_to_back => 3200,
+                _ => 2000,
+            };
+        }
+
+        points *= level;
+        self.score += points;
+        self.lines += lines;
+
+        Award {
+            points,
+            t_spin,
+            combo: Some(combo).filter(|c| *c > 0),
+            back_to_back,
+            perfect_clear,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn line_clear_test() {
+        let mut scoring = Scoring::default();
+        assert_eq!(scoring.lock(1, 1, None, false).points, 100);
+        assert_eq!(scoring.lock(0, 1, None, false).points, 0);
+        assert_eq!(scoring.lock(2, 2, None, false).points, 600);
+        assert_eq!(scoring.lock(0, 1, None, false).points, 0);
+        assert_eq!(scoring.lock(3, 1, None, false).points, 500);
+        assert_eq!(scoring.lock(0, 1, None, false).points, 0);
+        assert_eq!(scoring.lock(4, 3, None, false).points, 2400);
+        assert_eq!(scoring.score(), 3600);
+        assert_eq!(scoring.lines(), 10);
+
+        scoring.soft_drop(5);
+        scoring.hard_drop(10);
+        assert_eq!(scoring.score(), 3625);
+    }
+
+    #[test]
+    fn back_to_back_test() {
+        let mut scoring = Scoring::default();
+        scoring.lock(4, 1, None, false);
+        scoring.lock(0, 1, None, false);
+
+        let award = scoring.lock(4, 1, None, false);
+        assert!(award.back_to_back);
+        assert_eq!(award.points, 1200);
+
+        scoring.lock(0, 1, None, false);
+        scoring.lock(1, 1, None, false);
+        scoring.lock(0, 1, None, false);
+        let award = scoring.lock(4, 1, None, false);
+        assert!(!award.back_to_back);
+        assert_eq!(award.points, 800);
+    }
+
+    #[test]
+    fn combo_test() {
+        let mut scoring = Scoring::default();
+        assert_eq!(scoring.lock(1, 1, None, false).combo, None);
+        assert_eq!(scoring.lock(1, 1, None, false).points, 150);
+        let award = scoring.lock(2, 1, None, false);
+        assert_eq!(award.combo, Some(2));
+        assert_eq!(award.points, 400);
+        assert_eq!(scoring.combo(), Some(2));
+
+        scoring.lock(0, 1, None, false);
+        assert_eq!(scoring.combo(), None);
+        assert_eq!(scoring.lock(1, 1, None, false).points, 100);
+    }
+
+    #[test]
+    fn perfect_clear_test() {
+        let mut scoring = Scoring::default();
+        let award = scoring.lock(2, 2, None, true);
+        assert!(award.perfect_clear);
+        assert_eq!(award.points, 3000);
+
+        scoring.lock(0, 1, None, false);
+        scoring.lock(4, 1, None, false);
+        scoring.lock(0, 1, None, false);
+        assert_eq!(scoring.lock(4, 1, None, true).points, 1200 + 3200);
+    }
+
+    #[test]
+    fn t_spin_test() {
+        let mut scoring = Scoring::default();
+        let award = scoring.lock(0, 2, Some(TSpin::Full), false);
+        assert_eq!(award.t_spin, Some(TSpin::Full));
+        assert_eq!(award.points, 800);
+        assert_eq!(scoring.lock(0, 1, Some(TSpin::Mini), false).points, 100);
+        assert_eq!(scoring.lock(1, 1, Some(TSpin::Mini), false).points, 200);
+
+        let award = scoring.lock(2, 1, Some(TSpin::Full), false);
+        assert!(award.back_to_back);
+        assert_eq!(award.points, 1200 * 3 / 2 + 50);
+
+        scoring.lock(0, 1, Some(TSpin::Full), false);
+        let award = scoring.lock(4, 1, None, false);
+        assert!(award.back_to_back);
+        assert_eq!(award.points, 1200);
+        assert_eq!(scoring.score(), 800 + 100 + 200 + 1850 + 400 + 1200);
+    }
+}
@@ -1,27 +1,49 @@
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub(crate) enum Direction {
+/// Rotation state of a shape: `Top` is the spawn orientation, `Right` and `Left` are one
+/// clockwise and one counterclockwise turn away from it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
     Top,
     Bottom,
     Left,
     Right,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub(crate) struct Position {
-    pub(crate) x: usize,
-    pub(crate) y: usize,
+impl Direction {
+    fn index(&self) -> usize {
+        match self {
+            Direction::Top => 0,
+            Direction::Right => 1,
+            Direction::Bottom => 2,
+            Direction::Left => 3,
+        }
+    }
+
+    fn turned(&self, clockwise_turns: usize) -> Direction {
+        [
+            Direction::Top,
+            Direction::Right,
+            Direction::Bottom,
+            Direction::Left,
+        ][(self.index() + clockwise_turns) % 4]
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Position {
+    pub x: usize,
+    pub y: usize,
 }
 
 impl Position {
-    pub(crate) fn new(x: usize, y: usize) -> Self {
+    pub fn new(x: usize, y: usize) -> Self {
         Self { x, y }
     }
 }
 
-pub(crate) type ShapeBody = [Position; 4];
+pub type ShapeBody = [Position; 4];
 
-#[derive(Debug, Clone)]
-pub(crate) enum ShapeType {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShapeType {
     TForm,
     LForm,
     LMirrored,
@@ -31,1266 +53,452 @@ pub(crate) enum ShapeType {
     Quadrat,
 }
 
-#[derive(Debug, Clone)]
-pub(crate) struct Shape {
-    pub(crate) body: ShapeBody,
-    pub(crate) shape_type: ShapeType,
-    direction: Direction,
-}
-
-impl Shape {
-    pub(crate) fn new(t: ShapeType, d: Direction) -> Self {
-        match t {
-            ShapeType::Quadrat => quadrat(),
-            ShapeType::Line => line(d),
-            ShapeType::TForm => t_form(d),
-            ShapeType::LForm => l_form(d),
-            ShapeType::LMirrored => l_mirrored(d),
-            ShapeType::SForm => s_form(d),
-            ShapeType::SMirrored => s_mirrored(d),
-        }
-    }
-
-    pub(crate) fn left(&self) -> Option<Shape> {
-        let mut shape = self.clone();
-        for p in shape.body.iter_mut() {
-            p.x = p.x.checked_sub(1)?;
-        }
-
-        Some(shape)
-    }
-
-    pub(crate) fn right(&self) -> Option<Shape> {
-        let mut shape = self.clone();
-        for p in shape.body.iter_mut() {
-            p.x = p.x.checked_add(1)?;
+impl ShapeType {
+    pub const ALL: [ShapeType; 7] = [
+        ShapeType::TForm,
+        ShapeType::LForm,
+        ShapeType::LMirrored,
+        ShapeType::Line,
+        ShapeType::SForm,
+        ShapeType::SMirrored,
+        ShapeType::Quadrat,
+    ];
+
+    /// Maps the usual single letter piece names (`TLJISZO`) to shape types.
+    pub fn from_letter(c: char) -> Option<ShapeType> {
+        match c.to_ascii_uppercase() {
+            'T' => Some(ShapeType::TForm),
+            'L' => Some(ShapeType::LForm),
+            'J' => Some(ShapeType::LMirrored),
+            'I' => Some(ShapeType::Line),
+            'S' => Some(ShapeType::SForm),
+            'Z' => Some(ShapeType::SMirrored),
+            'O' => Some(ShapeType::Quadrat),
+            _ => None,
         }
-
-        Some(shape)
     }
 
-    pub(crate) fn down(&self) -> Option<Shape> {
-        let mut shape = self.clone();
-        for p in shape.body.iter_mut() {
-            p.y = p.y.checked_add(1)?;
+    pub fn letter(&self) -> char {
+        match self {
+            ShapeType::TForm => 'T',
+            ShapeType::LForm => 'L',
+            ShapeType::LMirrored => 'J',
+            ShapeType::Line => 'I',
+            ShapeType::SForm => 'S',
+            ShapeType::SMirrored => 'Z',
+            ShapeType::Quadrat => 'O',
         }
-
-        Some(shape)
     }
 
-    pub(crate) fn turn_left(&self) -> Option<Shape> {
-        let shape = self.clone();
-        match self.shape_type {
-            ShapeType::Line => line_turn_left(shape),
-            ShapeType::TForm => t_form_turn_left(shape),
-            ShapeType::LForm => l_form_turn_left(shape),
-            ShapeType::LMirrored => l_mirrored_turn_left(shape),
-            ShapeType::SForm => s_form_turn_left(shape),
-            ShapeType::SMirrored => s_mirrored_turn_left(shape),
-            ShapeType::Quadrat => Some(shape),
+    /// Size of the bounding box and the cells of the shape in its spawn orientation.
+    fn cells(&self) -> (isize, [(isize, isize); 4]) {
+        match self {
+            ShapeType::TForm => (3, [(1, 0), (0, 1), (1, 1), (2, 1)]),
+            ShapeType::LForm => (3, [(2, 0), (0, 1), (1, 1), (2, 1)]),
+            ShapeType::LMirrored => (3, [(0, 0), (0, 1), (1, 1), (2, 1)]),
+            ShapeType::Line => (4, [(0, 1), (1, 1), (2, 1), (3, 1)]),
+            ShapeType::SForm => (3, [(1, 0), (2, 0), (0, 1), (1, 1)]),
+            ShapeType::SMirrored => (3, [(0, 0), (1, 0), (1, 1), (2, 1)]),
+            ShapeType::Quadrat => (2, [(0, 0), (1, 0), (0, 1), (1, 1)]),
         }
     }
 }
 
-fn quadrat() -> Shape {
-    let x = 4;
-    let y = 0;
-    Shape {
-        shape_type: ShapeType::Quadrat,
-        direction: Direction::Top,
-        body: [
-            Position::new(x, y),
-            Position::new(x + 1, y),
-            Position::new(x, y + 1),
-            Position::new(x + 1, y + 1),
-        ],
-    }
+type Kicks = [[(isize, isize); 5]; 4];
+
+// SRS wall kicks tried in order, indexed by the direction the turn starts from. The board's
+// `y` grows downwards, so upward kicks are negative here unlike in the usual SRS tables.
+const JLSTZ_KICKS_RIGHT: Kicks = [
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)],
+];
+
+const JLSTZ_KICKS_LEFT: Kicks = [
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)],
+];
+
+const LINE_KICKS_RIGHT: Kicks = [
+    [(0, 0), (-2, 0), (1, 0), (-2, 1), (1, -2)],
+    [(0, 0), (-1, 0), (2, 0), (-1, -2), (2, 1)],
+    [(0, 0), (2, 0), (-1, 0), (2, -1), (-1, 2)],
+    [(0, 0), (1, 0), (-2, 0), (1, 2), (-2, -1)],
+];
+
+const LINE_KICKS_LEFT: Kicks = [
+    [(0, 0), (-1, 0), (2, 0), (-1, -2), (2, 1)],
+    [(0, 0), (2, 0), (-1, 0), (2, -1), (-1, 2)],
+    [(0, 0), (1, 0), (-2, 0), (1, 2), (-2, -1)],
+    [(0, 0), (-2, 0), (1, 0), (-2, 1), (1, -2)],
+];
+
+const QUADRAT_KICKS: Kicks = [[(0, 0); 5]; 4];
+
+// SRS has no half turns, these are the kicks popularised by TETR.IO's SRS+.
+const KICKS_180: [[(isize, isize); 6]; 4] = [
+    [(0, 0), (0, -1), (1, -1), (-1, -1), (1, 0), (-1, 0)],
+    [(0, 0), (1, 0), (1, -2), (1, -1), (0, -2), (0, -1)],
+    [(0, 0), (0, 1), (-1, 1), (1, 1), (-1, 0), (1, 0)],
+    [(0, 0), (-1, 0), (-1, -2), (-1, -1), (0, -2), (0, -1)],
+];
+
+#[derive(Debug, Clone)]
+pub struct Shape {
+    pub body: ShapeBody,
+    pub shape_type: ShapeType,
+    direction: Direction,
+    x: isize,
+    y: isize,
+    kick: (isize, isize),
+    /// Whether the last quarter turn needed the last of the SRS wall kicks.
+    last_kick: bool,
 }
 
-fn line(direction: Direction) -> Shape {
-    let x = if direction == Direction::Left || direction == Direction::Right {
-        4
-    } else {
-        5
-    };
-    let y = 0;
-
-    let body = match direction {
-        Direction::Top | Direction::Bottom => [
-            Position::new(x, y),
-            Position::new(x, y + 1),
-            Position::new(x, y + 2),
-            Position::new(x, y + 3),
-        ],
-        Direction::Left | Direction::Right => [
-            Position::new(x - 2, y),
-            Position::new(x - 1, y),
-            Position::new(x, y),
-            Position::new(x + 1, y),
-        ],
-    };
-
-    Shape {
-        shape_type: ShapeType::Line,
-        body,
-        direction,
+impl Shape {
+    /// Creates a shape at the spawn position of a board `board_width` columns wide,
+    /// centred and rounded to the left.
+    pub fn new(t: ShapeType, d: Direction, board_width: usize) -> Self {
+        let (size, _) = t.cells();
+        let x = (board_width as isize - size) / 2;
+        Self::place(t, d, x, 0).unwrap()
     }
-}
 
-fn line_turn_left(mut s: Shape) -> Option<Shape> {
-    match s.direction {
-        Direction::Top | Direction::Bottom => {
-            s.body[0].x = s.body[0].x.checked_sub(2)?;
-            s.body[0].y = s.body[0].y.checked_add(2)?;
-            s.body[1].x = s.body[1].x.checked_sub(1)?;
-            s.body[1].y = s.body[1].y.checked_add(1)?;
-            s.body[3].x = s.body[3].x.checked_add(1)?;
-            s.body[3].y = s.body[3].y.checked_sub(1)?;
-            s.direction = Direction::Left;
+    /// Places the bounding box of the shape at `x`, `y`, failing when a cell would end up
+    /// left of or above the board.
+    fn place(shape_type: ShapeType, direction: Direction, x: isize, y: isize) -> Option<Shape> {
+        let (size, cells) = shape_type.cells();
+        let mut body = ShapeBody::default();
+        for (p, (mut cx, mut cy)) in body.iter_mut().zip(cells) {
+            for _ in 0..direction.index() {
+                (cx, cy) = (size - 1 - cy, cx);
+            }
+            p.x = usize::try_from(x + cx).ok()?;
+            p.y = usize::try_from(y + cy).ok()?;
         }
-        Direction::Left | Direction::Right => {
-            s.body[0].x = s.body[0].x.checked_add(2)?;
-            s.body[0].y = s.body[0].y.checked_sub(2)?;
-            s.body[1].x = s.body[1].x.checked_add(1)?;
-            s.body[1].y = s.body[1].y.checked_sub(1)?;
-            s.body[3].x = s.body[3].x.checked_sub(1)?;
-            s.body[3].y = s.body[3].y.checked_add(1)?;
-            s.direction = Direction::Top;
-        }
-    };
-
-    Some(s)
-}
 
-fn t_form(direction: Direction) -> Shape {
-    let x = 5;
-    let y = 0;
-
-    let body = match direction {
-        Direction::Top => [
-            Position::new(x, y),
-            Position::new(x - 1, y + 1),
-            Position::new(x, y + 1),
-            Position::new(x + 1, y + 1),
-        ],
-        Direction::Bottom => [
-            Position::new(x, y + 1),
-            Position::new(x - 1, y),
-            Position::new(x, y),
-            Position::new(x + 1, y),
-        ],
-        Direction::Left => [
-            Position::new(x, y),
-            Position::new(x - 1, y + 1),
-            Position::new(x, y + 1),
-            Position::new(x, y + 2),
-        ],
-        Direction::Right => [
-            Position::new(x, y),
-            Position::new(x, y + 2),
-            Position::new(x, y + 1),
-            Position::new(x + 1, y + 1),
-        ],
-    };
-
-    Shape {
-        shape_type: ShapeType::TForm,
-        body,
-        direction,
+        Some(Shape {
+            body,
+            shape_type,
+            direction,
+            x,
+            y,
+            kick: (0, 0),
+            last_kick: false,
+        })
     }
-}
 
-fn t_form_turn_left(mut s: Shape) -> Option<Shape> {
-    match s.direction {
-        Direction::Top => {
-            s.body[3].x = s.body[3].x.checked_sub(1)?;
-            s.body[3].y = s.body[3].y.checked_add(1)?;
-            s.direction = Direction::Left;
-        }
-        Direction::Bottom => {
-            s.body[0].y = s.body[0].y.checked_sub(2)?;
-            s.body[1].x = s.body[1].x.checked_add(1)?;
-            s.body[1].y = s.body[1].y.checked_add(1)?;
-            s.direction = Direction::Right;
-        }
-        Direction::Left => {
-            s.body[0].y = s.body[0].y.checked_add(2)?;
-            s.body[3].x = s.body[3].x.checked_add(1)?;
-            s.body[3].y = s.body[3].y.checked_sub(1)?;
-            s.direction = Direction::Bottom;
-        }
-        Direction::Right => {
-            s.body[1].x = s.body[1].x.checked_sub(1)?;
-            s.body[1].y = s.body[1].y.checked_sub(1)?;
-            s.direction = Direction::Top;
-        }
+    pub fn direction(&self) -> Direction {
+        self.direction
     }
 
-    Some(s)
-}
+    /// The offset of the wall kick the shape was turned with, `(0, 0)` when it turned in
+    /// place or was not turned.
+    pub fn kick(&self) -> (isize, isize) {
+        self.kick
+    }
 
-fn l_form(direction: Direction) -> Shape {
-    let x = 4;
-    let y = 0;
-
-    let body = match direction {
-        Direction::Top => [
-            Position::new(x, y),
-            Position::new(x, y + 1),
-            Position::new(x, y + 2),
-            Position::new(x + 1, y + 2),
-        ],
-        Direction::Bottom => [
-            Position::new(x, y + 2),
-            Position::new(x, y + 1),
-            Position::new(x, y),
-            Position::new(x - 1, y),
-        ],
-        Direction::Left => [
-            Position::new(x - 2, y + 1),
-            Position::new(x - 1, y + 1),
-            Position::new(x, y + 1),
-            Position::new(x, y),
-        ],
-        Direction::Right => [
-            Position::new(x + 2, y),
-            Position::new(x + 1, y),
-            Position::new(x, y),
-            Position::new(x, y + 1),
-        ],
-    };
-
-    Shape {
-        shape_type: ShapeType::LForm,
-        body,
-        direction,
+    /// Whether the shape was turned a quarter with the last wall kick SRS tries, one column
+    /// and two rows away, which the T-spin rules reward.
+    pub fn last_kick(&self) -> bool {
+        self.last_kick
     }
-}
 
-fn l_form_turn_left(mut s: Shape) -> Option<Shape> {
-    match s.direction {
-        Direction::Top => {
-            s.body[0].x = s.body[0].x.checked_sub(1)?;
-            s.body[0].y = s.body[0].y.checked_add(1)?;
-            s.body[2].x = s.body[2].x.checked_add(1)?;
-            s.body[2].y = s.body[2].y.checked_sub(1)?;
-            s.body[3].y = s.body[3].y.checked_sub(2)?;
-            s.direction = Direction::Left;
-        }
-        Direction::Bottom => {
-            s.body[0].x = s.body[0].x.checked_add(1)?;
-            s.body[0].y = s.body[0].y.checked_sub(1)?;
-            s.body[2].x = s.body[2].x.checked_sub(1)?;
-            s.body[2].y = s.body[2].y.checked_add(1)?;
-            s.body[3].y = s.body[3].y.checked_add(2)?;
-            s.direction = Direction::Right;
-        }
-        Direction::Left => {
-            s.body[0].x = s.body[0].x.checked_add(1)?;
-            s.body[0].y = s.body[0].y.checked_add(1)?;
-            s.body[2].x = s.body[2].x.checked_sub(1)?;
-            s.body[2].y = s.body[2].y.checked_sub(1)?;
-            s.body[3].x = s.body[3].x.checked_sub(2)?;
-            s.direction = Direction::Bottom;
-        }
-        Direction::Right => {
-            s.body[0].x = s.body[0].x.checked_sub(1)?;
-            s.body[0].y = s.body[0].y.checked_sub(1)?;
-            s.body[2].x = s.body[2].x.checked_add(1)?;
-            s.body[2].y = s.body[2].y.checked_add(1)?;
-            s.body[3].x = s.body[3].x.checked_add(2)?;
-            s.direction = Direction::Top;
-        }
+    /// The corners of the shape's bounding box, top left first and clockwise.
+    pub fn corners(&self) -> [(isize, isize); 4] {
+        let (size, _) = self.shape_type.cells();
+        let (left, top) = (self.x, self.y);
+        let (right, bottom) = (self.x + size - 1, self.y + size - 1);
+        [(left, top), (right, top), (right, bottom), (left, bottom)]
     }
 
-    Some(s)
-}
+    pub fn left(&self) -> Option<Shape> {
+        Self::place(self.shape_type, self.direction, self.x - 1, self.y)
+    }
 
-fn l_mirrored(direction: Direction) -> Shape {
-    let x = 5;
-    let y = 0;
-
-    let body = match direction {
-        Direction::Top => [
-            Position::new(x, y),
-            Position::new(x, y + 1),
-            Position::new(x, y + 2),
-            Position::new(x - 1, y + 2),
-        ],
-        Direction::Bottom => [
-            Position::new(x, y + 2),
-            Position::new(x, y + 1),
-            Position::new(x, y),
-            Position::new(x + 1, y),
-        ],
-        Direction::Left => [
-            Position::new(x - 2, y),
-            Position::new(x - 1, y),
-            Position::new(x, y),
-            Position::new(x, y + 1),
-        ],
-        Direction::Right => [
-            Position::new(x + 2, y + 1),
-            Position::new(x + 1, y + 1),
-            Position::new(x, y + 1),
-            Position::new(x, y),
-        ],
-    };
-
-    Shape {
-        shape_type: ShapeType::LMirrored,
-        body,
-        direction,
+    pub fn right(&self) -> Option<Shape> {
+        Self::place(self.shape_type, self.direction, self.x + 1, self.y)
     }
-}
 
-fn l_mirrored_turn_left(mut s: Shape) -> Option<Shape> {
-    match s.direction {
-        Direction::Top => {
-            s.body[0].x = s.body[0].x.checked_sub(1)?;
-            s.body[0].y = s.body[0].y.checked_add(1)?;
-            s.body[2].x = s.body[2].x.checked_add(1)?;
-            s.body[2].y = s.body[2].y.checked_sub(1)?;
-            s.body[3].x = s.body[3].x.checked_add(2)?;
-            s.direction = Direction::Left;
-        }
-        Direction::Bottom => {
-            s.body[0].x = s.body[0].x.checked_add(1)?;
-            s.body[0].y = s.body[0].y.checked_sub(1)?;
-            s.body[2].x = s.body[2].x.checked_sub(1)?;
-            s.body[2].y = s.body[2].y.checked_add(1)?;
-            s.body[3].x = s.body[3].x.checked_sub(2)?;
-            s.direction = Direction::Right;
-        }
-        Direction::Left => {
-            s.body[0].x = s.body[0].x.checked_add(1)?;
-            s.body[0].y = s.body[0].y.checked_add(1)?;
-            s.body[2].x = s.body[2].x.checked_sub(1)?;
-            s.body[2].y = s.body[2].y.checked_sub(1)?;
-            s.body[3].y = s.body[3].y.checked_sub(2)?;
-            s.direction = Direction::Bottom;
-        }
-        Direction::Right => {
-            s.body[0].x = s.body[0].x.checked_sub(1)?;
-            s.body[0].y = s.body[0].y.checked_sub(1)?;
-            s.body[2].x = s.body[2].x.checked_add(1)?;
-            s.body[2].y = s.body[2].y.checked_add(1)?;
-            s.body[3].y = s.body[3].y.checked_add(2)?;
-            s.direction = Direction::Top;
-        }
+    pub fn down(&self) -> Option<Shape> {
+        Self::place(self.shape_type, self.direction, self.x, self.y + 1)
     }
 
-    Some(s)
-}
+    /// Turns the shape counterclockwise, returning the first wall kick for which `fits`
+    /// accepts the body.
+    pub fn turn_left(&self, fits: impl Fn(&ShapeBody) -> bool) -> Option<Shape> {
+        let kicks = match self.shape_type {
+            ShapeType::Line => &LINE_KICKS_LEFT,
+            ShapeType::Quadrat => &QUADRAT_KICKS,
+            _ => &JLSTZ_KICKS_LEFT,
+        };
 
-fn s_form(direction: Direction) -> Shape {
-    let x = 4;
-    let y = 0;
-
-    let body = match direction {
-        Direction::Top | Direction::Bottom => [
-            Position::new(x, y),
-            Position::new(x, y + 1),
-            Position::new(x + 1, y + 1),
-            Position::new(x + 1, y + 2),
-        ],
-        Direction::Left | Direction::Right => [
-            Position::new(x - 1, y + 1),
-            Position::new(x, y + 1),
-            Position::new(x, y),
-            Position::new(x + 1, y),
-        ],
-    };
-
-    Shape {
-        shape_type: ShapeType::SForm,
-        body,
-        direction,
+        self.turn(3, &kicks[self.direction.index()], fits)
     }
-}
 
-fn s_form_turn_left(mut s: Shape) -> Option<Shape> {
-    match s.direction {
-        Direction::Top | Direction::Bottom => {
-            s.body[0].y = s.body[0].y.checked_add(1)?;
-            s.body[1].x = s.body[1].x.checked_add(1)?;
-            s.body[2].y = s.body[2].y.checked_sub(1)?;
-            s.body[3].x = s.body[3].x.checked_add(1)?;
-            s.body[3].y = s.body[3].y.checked_sub(2)?;
-            s.direction = Direction::Left;
-        }
-        Direction::Left | Direction::Right => {
-            s.body[0].y = s.body[0].y.checked_sub(1)?;
-            s.body[1].x = s.body[1].x.checked_sub(1)?;
-            s.body[2].y = s.body[2].y.checked_add(1)?;
-            s.body[3].x = s.body[3].x.checked_sub(1)?;
-            s.body[3].y = s.body[3].y.checked_add(2)?;
-            s.direction = Direction::Top;
-        }
+    /// Turns the shape clockwise, returning the first wall kick for which `fits` accepts
+    /// the body.
+    pub fn turn_right(&self, fits: impl Fn(&ShapeBody) -> bool) -> Option<Shape> {
+        let kicks = match self.shape_type {
+            ShapeType::Line => &LINE_KICKS_RIGHT,
+            ShapeType::Quadrat => &QUADRAT_KICKS,
+            _ => &JLSTZ_KICKS_RIGHT,
+        };
+
+        self.turn(1, &kicks[self.direction.index()], fits)
     }
 
-    Some(s)
-}
+    pub fn turn_180(&self, fits: impl Fn(&ShapeBody) -> bool) -> Option<Shape> {
+        let kicks: &[(isize, isize)] = match self.shape_type {
+            ShapeType::Quadrat => &[(0, 0)],
+            _ => &KICKS_180[self.direction.index()],
+        };
 
-fn s_mirrored(direction: Direction) -> Shape {
-    let x = 5;
-    let y = 0;
-
-    let body = match direction {
-        Direction::Top | Direction::Bottom => [
-            Position::new(x, y),
-            Position::new(x, y + 1),
-            Position::new(x - 1, y + 1),
-            Position::new(x - 1, y + 2),
-        ],
-        Direction::Left | Direction::Right => [
-            Position::new(x - 1, y),
-            Position::new(x, y),
-            Position::new(x, y + 1),
-            Position::new(x + 1, y + 1),
-        ],
-    };
-
-    Shape {
-        shape_type: ShapeType::SMirrored,
-        body,
-        direction,
+        self.turn(2, kicks, fits)
     }
-}
 
-fn s_mirrored_turn_left(mut s: Shape) -> Option<Shape> {
-    match s.direction {
-        Direction::Top | Direction::Bottom => {
-            s.body[0].x = s.body[0].x.checked_sub(1)?;
-            s.body[1].y = s.body[1].y.checked_sub(1)?;
-            s.body[2].x = s.body[2].x.checked_add(1)?;
-            s.body[3].x = s.body[3].x.checked_add(2)?;
-            s.body[3].y = s.body[3].y.checked_sub(1)?;
-            s.direction = Direction::Left;
-        }
-        Direction::Left | Direction::Right => {
-            s.body[0].x = s.body[0].x.checked_add(1)?;
-            s.body[1].y = s.body[1].y.checked_add(1)?;
-            s.body[2].x = s.body[2].x.checked_sub(1)?;
-            s.body[3].x = s.body[3].x.checked_sub(2)?;
-            s.body[3].y = s.body[3].y.checked_add(1)?;
-            s.direction = Direction::Top;
-        }
+    fn turn(
+        &self,
+        clockwise_turns: usize,
+        kicks: &[(isize, isize)],
+        fits: impl Fn(&ShapeBody) -> bool,
+    ) -> Option<Shape> {
+        let direction = self.direction.turned(clockwise_turns);
+        kicks
+            .iter()
+            .enumerate()
+            .filter_map(|(i, &(dx, dy))| {
+                let shape = Self::place(self.shape_type, direction, self.x + dx, self.y + dy)?;
+                Some(Shape {
+                    kick: (dx, dy),
+                    last_kick: clockwise_turns != 2 && i == kicks.len() - 1,
+                    ..shape
+                })
+            })
+            .find(|s| fits(&s.body))
     }
-
-    Some(s)
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
 
-    #[test]
-    fn quadrat_test() {
-        let f = quadrat();
-        assert_eq!(f.body[0].x, 4);
-        assert_eq!(f.body[0].y, 0);
-
-        assert_eq!(f.body[1].x, 5);
-        assert_eq!(f.body[1].y, 0);
+    fn cells(f: &Shape) -> Vec<(usize, usize)> {
+        let mut cells = f.body.iter().map(|p| (p.x, p.y)).collect::<Vec<_>>();
+        cells.sort_by_key(|(x, y)| (*y, *x));
+        cells
+    }
 
-        assert_eq!(f.body[2].x, 4);
-        assert_eq!(f.body[2].y, 1);
+    fn free(_: &ShapeBody) -> bool {
+        true
+    }
 
-        assert_eq!(f.body[3].x, 5);
-        assert_eq!(f.body[3].y, 1);
+    #[test]
+    fn quadrat_test() {
+        let f = Shape::new(ShapeType::Quadrat, Direction::Top, 10);
+        assert_eq!(cells(&f), vec![(4, 0), (5, 0), (4, 1), (5, 1)]);
 
         let f = f.right().unwrap();
-        assert_eq!(f.body[0].x, 5);
-        assert_eq!(f.body[0].y, 0);
-
-        assert_eq!(f.body[1].x, 6);
-        assert_eq!(f.body[1].y, 0);
-
-        assert_eq!(f.body[2].x, 5);
-        assert_eq!(f.body[2].y, 1);
-
-        assert_eq!(f.body[3].x, 6);
-        assert_eq!(f.body[3].y, 1);
+        assert_eq!(cells(&f), vec![(5, 0), (6, 0), (5, 1), (6, 1)]);
 
         let f = f.left().unwrap();
-        assert_eq!(f.body[0].x, 4);
-        assert_eq!(f.body[0].y, 0);
-
-        assert_eq!(f.body[1].x, 5);
-        assert_eq!(f.body[1].y, 0);
-
-        assert_eq!(f.body[2].x, 4);
-        assert_eq!(f.body[2].y, 1);
-
-        assert_eq!(f.body[3].x, 5);
-        assert_eq!(f.body[3].y, 1);
+        assert_eq!(cells(&f), vec![(4, 0), (5, 0), (4, 1), (5, 1)]);
 
         let f = f.down().unwrap();
-        assert_eq!(f.body[0].x, 4);
-        assert_eq!(f.body[0].y, 1);
+        assert_eq!(cells(&f), vec![(4, 1), (5, 1), (4, 2), (5, 2)]);
 
-        assert_eq!(f.body[1].x, 5);
-        assert_eq!(f.body[1].y, 1);
-
-        assert_eq!(f.body[2].x, 4);
-        assert_eq!(f.body[2].y, 2);
-
-        assert_eq!(f.body[3].x, 5);
-        assert_eq!(f.body[3].y, 2);
-
-        let f = f.turn_left().unwrap();
-        assert_eq!(f.body[0].x, 4);
-        assert_eq!(f.body[0].y, 1);
-
-        assert_eq!(f.body[1].x, 5);
-        assert_eq!(f.body[1].y, 1);
-
-        assert_eq!(f.body[2].x, 4);
-        assert_eq!(f.body[2].y, 2);
-
-        assert_eq!(f.body[3].x, 5);
-        assert_eq!(f.body[3].y, 2);
+        let f = f.turn_left(free).unwrap();
+        assert_eq!(cells(&f), vec![(4, 1), (5, 1), (4, 2), (5, 2)]);
+        assert_eq!(f.direction(), Direction::Left);
     }
 
     #[test]
     fn line_test() {
-        let f = line(Direction::Left);
-        assert_eq!(f.body[0].x, 2);
-        assert_eq!(f.body[0].y, 0);
-
-        assert_eq!(f.body[1].x, 3);
-        assert_eq!(f.body[1].y, 0);
-
-        assert_eq!(f.body[2].x, 4);
-        assert_eq!(f.body[2].y, 0);
-
-        assert_eq!(f.body[3].x, 5);
-        assert_eq!(f.body[3].y, 0);
-
-        let f = line(Direction::Top);
-        assert_eq!(f.body[0].x, 5);
-        assert_eq!(f.body[0].y, 0);
-
-        assert_eq!(f.body[1].x, 5);
-        assert_eq!(f.body[1].y, 1);
-
-        assert_eq!(f.body[2].x, 5);
-        assert_eq!(f.body[2].y, 2);
-
-        assert_eq!(f.body[3].x, 5);
-        assert_eq!(f.body[3].y, 3);
-
-        let f = f.right().unwrap();
-        assert_eq!(f.body[0].x, 6);
-        assert_eq!(f.body[0].y, 0);
-
-        assert_eq!(f.body[1].x, 6);
-        assert_eq!(f.body[1].y, 1);
-
-        assert_eq!(f.body[2].x, 6);
-        assert_eq!(f.body[2].y, 2);
-
-        assert_eq!(f.body[3].x, 6);
-        assert_eq!(f.body[3].y, 3);
+        let f = Shape::new(ShapeType::Line, Direction::Top, 10);
+        assert_eq!(cells(&f), vec![(3, 1), (4, 1), (5, 1), (6, 1)]);
 
-        let f = f.down().unwrap();
-        assert_eq!(f.body[0].x, 6);
-        assert_eq!(f.body[0].y, 1);
-
-        assert_eq!(f.body[1].x, 6);
-        assert_eq!(f.body[1].y, 2);
-
-        assert_eq!(f.body[2].x, 6);
-        assert_eq!(f.body[2].y, 3);
-
-        assert_eq!(f.body[3].x, 6);
-        assert_eq!(f.body[3].y, 4);
-
-        let f = f.left().unwrap();
-        assert_eq!(f.body[0].x, 5);
-        assert_eq!(f.body[0].y, 1);
-
-        assert_eq!(f.body[1].x, 5);
-        assert_eq!(f.body[1].y, 2);
-
-        assert_eq!(f.body[2].x, 5);
-        assert_eq!(f.body[2].y, 3);
-
-        assert_eq!(f.body[3].x, 5);
-        assert_eq!(f.body[3].y, 4);
+        let f = Shape::new(ShapeType::Line, Direction::Right, 10);
+        assert_eq!(cells(&f), vec![(5, 0), (5, 1), (5, 2), (5, 3)]);
 
-        let f = f.turn_left().unwrap();
-        assert_eq!(f.body[0].x, 3);
-        assert_eq!(f.body[0].y, 3);
+        let f = Shape::new(ShapeType::Line, Direction::Bottom, 10);
+        assert_eq!(cells(&f), vec![(3, 2), (4, 2), (5, 2), (6, 2)]);
 
-        assert_eq!(f.body[1].x, 4);
-        assert_eq!(f.body[1].y, 3);
+        let f = Shape::new(ShapeType::Line, Direction::Left, 10);
+        assert_eq!(cells(&f), vec![(4, 0), (4, 1), (4, 2), (4, 3)]);
 
-        assert_eq!(f.body[2].x, 5);
-        assert_eq!(f.body[2].y, 3);
+        let f = f.right().unwrap().down().unwrap();
+        assert_eq!(cells(&f), vec![(5, 1), (5, 2), (5, 3), (5, 4)]);
 
-        assert_eq!(f.body[3].x, 6);
-        assert_eq!(f.body[3].y, 3);
+        let f = f.turn_left(free).unwrap();
+        assert_eq!(f.direction(), Direction::Bottom);
+        assert_eq!(cells(&f), vec![(4, 3), (5, 3), (6, 3), (7, 3)]);
 
-        let f = f.turn_left().unwrap();
-        assert_eq!(f.body[0].x, 5);
-        assert_eq!(f.body[0].y, 1);
-
-        assert_eq!(f.body[1].x, 5);
-        assert_eq!(f.body[1].y, 2);
-
-        assert_eq!(f.body[2].x, 5);
-        assert_eq!(f.body[2].y, 3);
-
-        assert_eq!(f.body[3].x, 5);
-        assert_eq!(f.body[3].y, 4);
+        let f = f.turn_left(free).unwrap();
+        assert_eq!(f.direction(), Direction::Right);
+        assert_eq!(cells(&f), vec![(6, 1), (6, 2), (6, 3), (6, 4)]);
     }
 
     #[test]
     fn t_form_test() {
-        let f = t_form(Direction::Left);
-        assert_eq!(f.body[0].x, 5);
-        assert_eq!(f.body[0].y, 0);
-
-        assert_eq!(f.body[1].x, 4);
-        assert_eq!(f.body[1].y, 1);
-
-        assert_eq!(f.body[2].x, 5);
-        assert_eq!(f.body[2].y, 1);
-
-        assert_eq!(f.body[3].x, 5);
-        assert_eq!(f.body[3].y, 2);
-
-        let f = t_form(Direction::Right);
-        assert_eq!(f.body[0].x, 5);
-        assert_eq!(f.body[0].y, 0);
-
-        assert_eq!(f.body[1].x, 5);
-        assert_eq!(f.body[1].y, 2);
-
-        assert_eq!(f.body[2].x, 5);
-        assert_eq!(f.body[2].y, 1);
-
-        assert_eq!(f.body[3].x, 6);
-        assert_eq!(f.body[3].y, 1);
-
-        let f = t_form(Direction::Bottom);
-        assert_eq!(f.body[0].x, 5);
-        assert_eq!(f.body[0].y, 1);
-
-        assert_eq!(f.body[1].x, 4);
-        assert_eq!(f.body[1].y, 0);
-
-        assert_eq!(f.body[2].x, 5);
-        assert_eq!(f.body[2].y, 0);
-
-        assert_eq!(f.body[3].x, 6);
-        assert_eq!(f.body[3].y, 0);
-
-        let f = t_form(Direction::Top);
-        assert_eq!(f.body[0].x, 5);
-        assert_eq!(f.body[0].y, 0);
-
-        assert_eq!(f.body[1].x, 4);
-        assert_eq!(f.body[1].y, 1);
-
-        assert_eq!(f.body[2].x, 5);
-        assert_eq!(f.body[2].y, 1);
-
-        assert_eq!(f.body[3].x, 6);
-        assert_eq!(f.body[3].y, 1);
-
-        let f = f.down().unwrap();
-        assert_eq!(f.body[0].x, 5);
-        assert_eq!(f.body[0].y, 1);
-
-        assert_eq!(f.body[1].x, 4);
-        assert_eq!(f.body[1].y, 2);
-
-        assert_eq!(f.body[2].x, 5);
-        assert_eq!(f.body[2].y, 2);
-
-        assert_eq!(f.body[3].x, 6);
-        assert_eq!(f.body[3].y, 2);
-
-        let f = f.right().unwrap();
-        assert_eq!(f.body[0].x, 6);
-        assert_eq!(f.body[0].y, 1);
-
-        assert_eq!(f.body[1].x, 5);
-        assert_eq!(f.body[1].y, 2);
-
-        assert_eq!(f.body[2].x, 6);
-        assert_eq!(f.body[2].y, 2);
-
-        assert_eq!(f.body[3].x, 7);
-        assert_eq!(f.body[3].y, 2);
-
-        let f = f.left().unwrap();
-        assert_eq!(f.body[0].x, 5);
-        assert_eq!(f.body[0].y, 1);
-
-        assert_eq!(f.body[1].x, 4);
-        assert_eq!(f.body[1].y, 2);
-
-        assert_eq!(f.body[2].x, 5);
-        assert_eq!(f.body[2].y, 2);
-
-        assert_eq!(f.body[3].x, 6);
-        assert_eq!(f.body[3].y, 2);
-
-        let f = f.turn_left().unwrap();
-        assert_eq!(f.body[0].x, 5);
-        assert_eq!(f.body[0].y, 1);
-
-        assert_eq!(f.body[1].x, 4);
-        assert_eq!(f.body[1].y, 2);
-
-        assert_eq!(f.body[2].x, 5);
-        assert_eq!(f.body[2].y, 2);
-
-        assert_eq!(f.body[3].x, 5);
-        assert_eq!(f.body[3].y, 3);
-
-        let f = f.turn_left().unwrap();
-        assert_eq!(f.body[0].x, 5);
-        assert_eq!(f.body[0].y, 3);
-
-        assert_eq!(f.body[1].x, 4);
-        assert_eq!(f.body[1].y, 2);
-
-        assert_eq!(f.body[2].x, 5);
-        assert_eq!(f.body[2].y, 2);
-
-        assert_eq!(f.body[3].x, 6);
-        assert_eq!(f.body[3].y, 2);
-
-        let f = f.turn_left().unwrap();
-        assert_eq!(f.body[0].x, 5);
-        assert_eq!(f.body[0].y, 1);
-
-        assert_eq!(f.body[1].x, 5);
-        assert_eq!(f.body[1].y, 3);
-
-        assert_eq!(f.body[2].x, 5);
-        assert_eq!(f.body[2].y, 2);
-
-        assert_eq!(f.body[3].x, 6);
-        assert_eq!(f.body[3].y, 2);
+        let f = Shape::new(ShapeType::TForm, Direction::Top, 10);
+        assert_eq!(cells(&f), vec![(4, 0), (3, 1), (4, 1), (5, 1)]);
 
-        let f = f.turn_left().unwrap();
-        assert_eq!(f.body[0].x, 5);
-        assert_eq!(f.body[0].y, 1);
+        let f = f.turn_left(free).unwrap();
+        assert_eq!(f.direction(), Direction::Left);
+        assert_eq!(cells(&f), vec![(4, 0), (3, 1), (4, 1), (4, 2)]);
 
-        assert_eq!(f.body[1].x, 4);
-        assert_eq!(f.body[1].y, 2);
+        let f = f.turn_left(free).unwrap();
+        assert_eq!(f.direction(), Direction::Bottom);
+        assert_eq!(cells(&f), vec![(3, 1), (4, 1), (5, 1), (4, 2)]);
 
-        assert_eq!(f.body[2].x, 5);
-        assert_eq!(f.body[2].y, 2);
+        let f = f.turn_left(free).unwrap();
+        assert_eq!(f.direction(), Direction::Right);
+        assert_eq!(cells(&f), vec![(4, 0), (4, 1), (5, 1), (4, 2)]);
 
-        assert_eq!(f.body[3].x, 6);
-        assert_eq!(f.body[3].y, 2);
+        let f = f.turn_left(free).unwrap();
+        assert_eq!(f.direction(), Direction::Top);
+        assert_eq!(cells(&f), vec![(4, 0), (3, 1), (4, 1), (5, 1)]);
     }
 
     #[test]
     fn l_form_test() {
-        let f = l_form(Direction::Left);
-        assert_eq!(f.body[0].x, 2);
-        assert_eq!(f.body[0].y, 1);
+        let f = Shape::new(ShapeType::LForm, Direction::Top, 10);
+        assert_eq!(cells(&f), vec![(5, 0), (3, 1), (4, 1), (5, 1)]);
 
-        assert_eq!(f.body[1].x, 3);
-        assert_eq!(f.body[1].y, 1);
+        let f = f.turn_left(free).unwrap();
+        assert_eq!(cells(&f), vec![(3, 0), (4, 0), (4, 1), (4, 2)]);
 
-        assert_eq!(f.body[2].x, 4);
-        assert_eq!(f.body[2].y, 1);
+        let f = f.turn_left(free).unwrap();
+        assert_eq!(cells(&f), vec![(3, 1), (4, 1), (5, 1), (3, 2)]);
 
-        assert_eq!(f.body[3].x, 4);
-        assert_eq!(f.body[3].y, 0);
-
-        let f = l_form(Direction::Right);
-        assert_eq!(f.body[0].x, 6);
-        assert_eq!(f.body[0].y, 0);
-
-        assert_eq!(f.body[1].x, 5);
-        assert_eq!(f.body[1].y, 0);
-
-        assert_eq!(f.body[2].x, 4);
-        assert_eq!(f.body[2].y, 0);
-
-        assert_eq!(f.body[3].x, 4);
-        assert_eq!(f.body[3].y, 1);
-
-        let f = l_form(Direction::Bottom);
-        assert_eq!(f.body[0].x, 4);
-        assert_eq!(f.body[0].y, 2);
-
-        assert_eq!(f.body[1].x, 4);
-        assert_eq!(f.body[1].y, 1);
-
-        assert_eq!(f.body[2].x, 4);
-        assert_eq!(f.body[2].y, 0);
-
-        assert_eq!(f.body[3].x, 3);
-        assert_eq!(f.body[3].y, 0);
-
-        let f = l_form(Direction::Top);
-        assert_eq!(f.body[0].x, 4);
-        assert_eq!(f.body[0].y, 0);
-
-        assert_eq!(f.body[1].x, 4);
-        assert_eq!(f.body[1].y, 1);
-
-        assert_eq!(f.body[2].x, 4);
-        assert_eq!(f.body[2].y, 2);
-
-        assert_eq!(f.body[3].x, 5);
-        assert_eq!(f.body[3].y, 2);
-
-        let f = f.down().unwrap();
-        assert_eq!(f.body[0].x, 4);
-        assert_eq!(f.body[0].y, 1);
-
-        assert_eq!(f.body[1].x, 4);
-        assert_eq!(f.body[1].y, 2);
-
-        assert_eq!(f.body[2].x, 4);
-        assert_eq!(f.body[2].y, 3);
-
-        assert_eq!(f.body[3].x, 5);
-        assert_eq!(f.body[3].y, 3);
-
-        let f = f.right().unwrap();
-        assert_eq!(f.body[0].x, 5);
-        assert_eq!(f.body[0].y, 1);
-
-        assert_eq!(f.body[1].x, 5);
-        assert_eq!(f.body[1].y, 2);
-
-        assert_eq!(f.body[2].x, 5);
-        assert_eq!(f.body[2].y, 3);
-
-        assert_eq!(f.body[3].x, 6);
-        assert_eq!(f.body[3].y, 3);
-
-        let f = f.left().unwrap();
-        assert_eq!(f.body[0].x, 4);
-        assert_eq!(f.body[0].y, 1);
-
-        assert_eq!(f.body[1].x, 4);
-        assert_eq!(f.body[1].y, 2);
-
-        assert_eq!(f.body[2].x, 4);
-        assert_eq!(f.body[2].y, 3);
-
-        assert_eq!(f.body[3].x, 5);
-        assert_eq!(f.body[3].y, 3);
-
-        // ___|
-        let f = f.turn_left().unwrap();
-        assert_eq!(f.body[0].x, 3);
-        assert_eq!(f.body[0].y, 2);
-
-        assert_eq!(f.body[1].x, 4);
-        assert_eq!(f.body[1].y, 2);
-
-        assert_eq!(f.body[2].x, 5);
-        assert_eq!(f.body[2].y, 2);
-
-        assert_eq!(f.body[3].x, 5);
-        assert_eq!(f.body[3].y, 1);
-
-        // _
-        //  |
-        //  |
-        //  |
-        let f = f.turn_left().unwrap();
-        assert_eq!(f.body[0].x, 4);
-        assert_eq!(f.body[0].y, 3);
-
-        assert_eq!(f.body[1].x, 4);
-        assert_eq!(f.body[1].y, 2);
-
-        assert_eq!(f.body[2].x, 4);
-        assert_eq!(f.body[2].y, 1);
-
-        assert_eq!(f.body[3].x, 3);
-        assert_eq!(f.body[3].y, 1);
-
-        //  ___
-        // |
-        let f = f.turn_left().unwrap();
-        assert_eq!(f.body[0].x, 5);
-        assert_eq!(f.body[0].y, 2);
-
-        assert_eq!(f.body[1].x, 4);
-        assert_eq!(f.body[1].y, 2);
-
-        assert_eq!(f.body[2].x, 3);
-        assert_eq!(f.body[2].y, 2);
-
-        assert_eq!(f.body[3].x, 3);
-        assert_eq!(f.body[3].y, 3);
-
-        // |
-        // |
-        // |_
-        let f = f.turn_left().unwrap();
-        assert_eq!(f.body[0].x, 4);
-        assert_eq!(f.body[0].y, 1);
-
-        assert_eq!(f.body[1].x, 4);
-        assert_eq!(f.body[1].y, 2);
-
-        assert_eq!(f.body[2].x, 4);
-        assert_eq!(f.body[2].y, 3);
-
-        assert_eq!(f.body[3].x, 5);
-        assert_eq!(f.body[3].y, 3);
+        let f = f.turn_left(free).unwrap();
+        assert_eq!(cells(&f), vec![(4, 0), (4, 1), (4, 2), (5, 2)]);
     }
 
     #[test]
     fn l_mirrored_test() {
-        let f = l_mirrored(Direction::Left);
-        assert_eq!(f.body[0].x, 3);
-        assert_eq!(f.body[0].y, 0);
-
-        assert_eq!(f.body[1].x, 4);
-        assert_eq!(f.body[1].y, 0);
-
-        assert_eq!(f.body[2].x, 5);
-        assert_eq!(f.body[2].y, 0);
-
-        assert_eq!(f.body[3].x, 5);
-        assert_eq!(f.body[3].y, 1);
-
-        let f = l_mirrored(Direction::Right);
-        assert_eq!(f.body[0].x, 7);
-        assert_eq!(f.body[0].y, 1);
-
-        assert_eq!(f.body[1].x, 6);
-        assert_eq!(f.body[1].y, 1);
-
-        assert_eq!(f.body[2].x, 5);
-        assert_eq!(f.body[2].y, 1);
-
-        assert_eq!(f.body[3].x, 5);
-        assert_eq!(f.body[3].y, 0);
-
-        let f = l_mirrored(Direction::Bottom);
-        assert_eq!(f.body[0].x, 5);
-        assert_eq!(f.body[0].y, 2);
-
-        assert_eq!(f.body[1].x, 5);
-        assert_eq!(f.body[1].y, 1);
-
-        assert_eq!(f.body[2].x, 5);
-        assert_eq!(f.body[2].y, 0);
-
-        assert_eq!(f.body[3].x, 6);
-        assert_eq!(f.body[3].y, 0);
-
-        let f = l_mirrored(Direction::Top);
-        assert_eq!(f.body[0].x, 5);
-        assert_eq!(f.body[0].y, 0);
-
-        assert_eq!(f.body[1].x, 5);
-        assert_eq!(f.body[1].y, 1);
-
-        assert_eq!(f.body[2].x, 5);
-        assert_eq!(f.body[2].y, 2);
-
-        assert_eq!(f.body[3].x, 4);
-        assert_eq!(f.body[3].y, 2);
-
-        let f = f.down().unwrap();
-        assert_eq!(f.body[0].x, 5);
-        assert_eq!(f.body[0].y, 1);
-
-        assert_eq!(f.body[1].x, 5);
-        assert_eq!(f.body[1].y, 2);
-
-        assert_eq!(f.body[2].x, 5);
-        assert_eq!(f.body[2].y, 3);
-
-        assert_eq!(f.body[3].x, 4);
-        assert_eq!(f.body[3].y, 3);
-
-        let f = f.right().unwrap();
-        assert_eq!(f.body[0].x, 6);
-        assert_eq!(f.body[0].y, 1);
-
-        assert_eq!(f.body[1].x, 6);
-        assert_eq!(f.body[1].y, 2);
-
-        assert_eq!(f.body[2].x, 6);
-        assert_eq!(f.body[2].y, 3);
-
-        assert_eq!(f.body[3].x, 5);
-        assert_eq!(f.body[3].y, 3);
-
-        let f = f.left().unwrap();
-        assert_eq!(f.body[0].x, 5);
-        assert_eq!(f.body[0].y, 1);
-
-        assert_eq!(f.body[1].x, 5);
-        assert_eq!(f.body[1].y, 2);
-
-        assert_eq!(f.body[2].x, 5);
-        assert_eq!(f.body[2].y, 3);
+        let f = Shape::new(ShapeType::LMirrored, Direction::Top, 10);
+        assert_eq!(cells(&f), vec![(3, 0), (3, 1), (4, 1), (5, 1)]);
 
-        assert_eq!(f.body[3].x, 4);
-        assert_eq!(f.body[3].y, 3);
+        let f = f.turn_left(free).unwrap();
+        assert_eq!(cells(&f), vec![(4, 0), (4, 1), (3, 2), (4, 2)]);
 
-        // ___
-        //   |
-        let f = f.turn_left().unwrap();
-        assert_eq!(f.body[0].x, 4);
-        assert_eq!(f.body[0].y, 2);
+        let f = f.turn_left(free).unwrap();
+        assert_eq!(cells(&f), vec![(3, 1), (4, 1), (5, 1), (5, 2)]);
 
-        assert_eq!(f.body[1].x, 5);
-        assert_eq!(f.body[1].y, 2);
-
-        assert_eq!(f.body[2].x, 6);
-        assert_eq!(f.body[2].y, 2);
-
-        assert_eq!(f.body[3].x, 6);
-        assert_eq!(f.body[3].y, 3);
-
-        //  _
-        // |
-        // |
-        // |
-        let f = f.turn_left().unwrap();
-        assert_eq!(f.body[0].x, 5);
-        assert_eq!(f.body[0].y, 3);
-
-        assert_eq!(f.body[1].x, 5);
-        assert_eq!(f.body[1].y, 2);
-
-        assert_eq!(f.body[2].x, 5);
-        assert_eq!(f.body[2].y, 1);
-
-        assert_eq!(f.body[3].x, 6);
-        assert_eq!(f.body[3].y, 1);
-
-        //  |
-        //  –––
-        let f = f.turn_left().unwrap();
-        assert_eq!(f.body[0].x, 6);
-        assert_eq!(f.body[0].y, 2);
-
-        assert_eq!(f.body[1].x, 5);
-        assert_eq!(f.body[1].y, 2);
-
-        assert_eq!(f.body[2].x, 4);
-        assert_eq!(f.body[2].y, 2);
-
-        assert_eq!(f.body[3].x, 4);
-        assert_eq!(f.body[3].y, 1);
-
-        //  |
-        //  |
-        // _|
-        let f = f.turn_left().unwrap();
-        assert_eq!(f.body[0].x, 5);
-        assert_eq!(f.body[0].y, 1);
-
-        assert_eq!(f.body[1].x, 5);
-        assert_eq!(f.body[1].y, 2);
-
-        assert_eq!(f.body[2].x, 5);
-        assert_eq!(f.body[2].y, 3);
-
-        assert_eq!(f.body[3].x, 4);
-        assert_eq!(f.body[3].y, 3);
+        let f = f.turn_left(free).unwrap();
+        assert_eq!(cells(&f), vec![(4, 0), (5, 0), (4, 1), (4, 2)]);
     }
 
     #[test]
     fn s_form_test() {
-        let f = s_form(Direction::Left);
-        assert_eq!(f.body[0].x, 3);
-        assert_eq!(f.body[0].y, 1);
-
-        assert_eq!(f.body[1].x, 4);
-        assert_eq!(f.body[1].y, 1);
-
-        assert_eq!(f.body[2].x, 4);
-        assert_eq!(f.body[2].y, 0);
-
-        assert_eq!(f.body[3].x, 5);
-        assert_eq!(f.body[3].y, 0);
-
-        let f = s_form(Direction::Top);
-        assert_eq!(f.body[0].x, 4);
-        assert_eq!(f.body[0].y, 0);
-
-        assert_eq!(f.body[1].x, 4);
-        assert_eq!(f.body[1].y, 1);
-
-        assert_eq!(f.body[2].x, 5);
-        assert_eq!(f.body[2].y, 1);
-
-        assert_eq!(f.body[3].x, 5);
-        assert_eq!(f.body[3].y, 2);
-
-        let f = f.down().unwrap();
-        assert_eq!(f.body[0].x, 4);
-        assert_eq!(f.body[0].y, 1);
-
-        assert_eq!(f.body[1].x, 4);
-        assert_eq!(f.body[1].y, 2);
-
-        assert_eq!(f.body[2].x, 5);
-        assert_eq!(f.body[2].y, 2);
-
-        assert_eq!(f.body[3].x, 5);
-        assert_eq!(f.body[3].y, 3);
-
-        let f = f.right().unwrap();
-        assert_eq!(f.body[0].x, 5);
-        assert_eq!(f.body[0].y, 1);
-
-        assert_eq!(f.body[1].x, 5);
-        assert_eq!(f.body[1].y, 2);
-
-        assert_eq!(f.body[2].x, 6);
-        assert_eq!(f.body[2].y, 2);
-
-        assert_eq!(f.body[3].x, 6);
-        assert_eq!(f.body[3].y, 3);
-
-        let f = f.left().unwrap();
-        assert_eq!(f.body[0].x, 4);
-        assert_eq!(f.body[0].y, 1);
-
-        assert_eq!(f.body[1].x, 4);
-        assert_eq!(f.body[1].y, 2);
-
-        assert_eq!(f.body[2].x, 5);
-        assert_eq!(f.body[2].y, 2);
-
-        assert_eq!(f.body[3].x, 5);
-        assert_eq!(f.body[3].y, 3);
+        let f = Shape::new(ShapeType::SForm, Direction::Top, 10);
+        assert_eq!(cells(&f), vec![(4, 0), (5, 0), (3, 1), (4, 1)]);
 
-        let f = f.turn_left().unwrap();
-        assert_eq!(f.body[0].x, 4);
-        assert_eq!(f.body[0].y, 2);
+        let f = f.turn_left(free).unwrap();
+        assert_eq!(cells(&f), vec![(3, 0), (3, 1), (4, 1), (4, 2)]);
 
-        assert_eq!(f.body[1].x, 5);
-        assert_eq!(f.body[1].y, 2);
-
-        assert_eq!(f.body[2].x, 5);
-        assert_eq!(f.body[2].y, 1);
-
-        assert_eq!(f.body[3].x, 6);
-        assert_eq!(f.body[3].y, 1);
-
-        let f = f.turn_left().unwrap();
-        assert_eq!(f.body[0].x, 4);
-        assert_eq!(f.body[0].y, 1);
-
-        assert_eq!(f.body[1].x, 4);
-        assert_eq!(f.body[1].y, 2);
-
-        assert_eq!(f.body[2].x, 5);
-        assert_eq!(f.body[2].y, 2);
-
-        assert_eq!(f.body[3].x, 5);
-        assert_eq!(f.body[3].y, 3);
+        let f = f.turn_left(free).unwrap();
+        assert_eq!(cells(&f), vec![(4, 1), (5, 1), (3, 2), (4, 2)]);
     }
 
     #[test]
     fn s_mirrored_test() {
-        let f = s_mirrored(Direction::Left);
-        assert_eq!(f.body[0].x, 4);
-        assert_eq!(f.body[0].y, 0);
-
-        assert_eq!(f.body[1].x, 5);
-        assert_eq!(f.body[1].y, 0);
-
-        assert_eq!(f.body[2].x, 5);
-        assert_eq!(f.body[2].y, 1);
-
-        assert_eq!(f.body[3].x, 6);
-        assert_eq!(f.body[3].y, 1);
+        let f = Shape::new(ShapeType::SMirrored, Direction::Top, 10);
+        assert_eq!(cells(&f), vec![(3, 0), (4, 0), (4, 1), (5, 1)]);
 
-        let f = s_mirrored(Direction::Top);
-        assert_eq!(f.body[0].x, 5);
-        assert_eq!(f.body[0].y, 0);
+        let f = f.turn_left(free).unwrap();
+        assert_eq!(cells(&f), vec![(4, 0), (3, 1), (4, 1), (3, 2)]);
 
-        assert_eq!(f.body[1].x, 5);
-        assert_eq!(f.body[1].y, 1);
-
-        assert_eq!(f.body[2].x, 4);
-        assert_eq!(f.body[2].y, 1);
-
-        assert_eq!(f.body[3].x, 4);
-        assert_eq!(f.body[3].y, 2);
-
-        let f = f.down().unwrap();
-        assert_eq!(f.body[0].x, 5);
-        assert_eq!(f.body[0].y, 1);
-
-        assert_eq!(f.body[1].x, 5);
-        assert_eq!(f.body[1].y, 2);
-
-        assert_eq!(f.body[2].x, 4);
-        assert_eq!(f.body[2].y, 2);
-
-        assert_eq!(f.body[3].x, 4);
-        assert_eq!(f.body[3].y, 3);
-
-        let f = f.right().unwrap();
-        assert_eq!(f.body[0].x, 6);
-        assert_eq!(f.body[0].y, 1);
-
-        assert_eq!(f.body[1].x, 6);
-        assert_eq!(f.body[1].y, 2);
-
-        assert_eq!(f.body[2].x, 5);
-        assert_eq!(f.body[2].y, 2);
-
-        assert_eq!(f.body[3].x, 5);
-        assert_eq!(f.body[3].y, 3);
-
-        let f = f.left().unwrap();
-        assert_eq!(f.body[0].x, 5);
-        assert_eq!(f.body[0].y, 1);
-
-        assert_eq!(f.body[1].x, 5);
-        assert_eq!(f.body[1].y, 2);
-
-        assert_eq!(f.body[2].x, 4);
-        assert_eq!(f.body[2].y, 2);
+        let f = f.turn_left(free).unwrap();
+        assert_eq!(cells(&f), vec![(3, 1), (4, 1), (4, 2), (5, 2)]);
+    }
 
-        assert_eq!(f.body[3].x, 4);
-        assert_eq!(f.body[3].y, 3);
+    #[test]
+    fn wall_kick_test() {
+        let mut f = Shape::new(ShapeType::TForm, Direction::Right, 10);
+        while let Some(next) = f.left() {
+            f = next;
+        }
+        assert_eq!(cells(&f), vec![(0, 0), (0, 1), (1, 1), (0, 2)]);
+
+        // The spawn orientation would stick out of the left wall, so the second kick
+        // moves it one column to the right.
+        let f = f.turn_left(|body| body.iter().all(|p| p.x < 10)).unwrap();
+        assert_eq!(f.direction(), Direction::Top);
+        assert_eq!(cells(&f), vec![(1, 0), (0, 1), (1, 1), (2, 1)]);
+        assert_eq!(f.kick(), (1, 0));
+        assert_eq!(f.right().unwrap().kick(), (0, 0));
+
+        // Without any room the turn is refused.
+        assert!(f.turn_left(|_| false).is_none());
+    }
 
-        let f = f.turn_left().unwrap();
-        assert_eq!(f.body[0].x, 4);
-        assert_eq!(f.body[0].y, 1);
+    #[test]
+    fn turn_right_test() {
+        let f = Shape::new(ShapeType::TForm, Direction::Top, 10);
 
-        assert_eq!(f.body[1].x, 5);
-        assert_eq!(f.body[1].y, 1);
+        let f = f.turn_right(free).unwrap();
+        assert_eq!(f.direction(), Direction::Right);
+        assert_eq!(cells(&f), vec![(4, 0), (4, 1), (5, 1), (4, 2)]);
 
-        assert_eq!(f.body[2].x, 5);
-        assert_eq!(f.body[2].y, 2);
+        let f = f.turn_right(free).unwrap();
+        assert_eq!(f.direction(), Direction::Bottom);
+        assert_eq!(cells(&f), vec![(3, 1), (4, 1), (5, 1), (4, 2)]);
 
-        assert_eq!(f.body[3].x, 6);
-        assert_eq!(f.body[3].y, 2);
+        let f = f.turn_left(free).unwrap().turn_left(free).unwrap();
+        assert_eq!(f.direction(), Direction::Top);
+        assert_eq!(cells(&f), vec![(4, 0), (3, 1), (4, 1), (5, 1)]);
 
-        let f = f.turn_left().unwrap();
-        assert_eq!(f.body[0].x, 5);
-        assert_eq!(f.body[0].y, 1);
+        let mut f = Shape::new(ShapeType::Line, Direction::Left, 10);
+        while let Some(next) = f.right().filter(|s| s.body.iter().all(|p| p.x < 10)) {
+            f = next;
+        }
+        assert_eq!(cells(&f), vec![(9, 0), (9, 1), (9, 2), (9, 3)]);
 
-        assert_eq!(f.body[1].x, 5);
-        assert_eq!(f.body[1].y, 2);
+        // Turning against the right wall needs the third kick, two columns to the left.
+        let f = f.turn_right(|body| body.iter().all(|p| p.x < 10)).unwrap();
+        assert_eq!(f.direction(), Direction::Top);
+        assert_eq!(cells(&f), vec![(6, 1), (7, 1), (8, 1), (9, 1)]);
+    }
 
-        assert_eq!(f.body[2].x, 4);
-        assert_eq!(f.body[2].y, 2);
+    #[test]
+    fn turn_180_test() {
+        let f = Shape::new(ShapeType::LForm, Direction::Top, 10)
+            .down()
+            .unwrap();
+
+        let f = f.turn_180(free).unwrap();
+        assert_eq!(f.direction(), Direction::Bottom);
+        assert_eq!(cells(&f), vec![(3, 2), (4, 2), (5, 2), (3, 3)]);
+
+        let f = f.turn_180(free).unwrap();
+        assert_eq!(f.direction(), Direction::Top);
+        assert_eq!(cells(&f), vec![(5, 1), (3, 2), (4, 2), (5, 2)]);
+
+        // Flipping on the floor lifts the shape by one row.
+        let f = Shape::new(ShapeType::LForm, Direction::Top, 10);
+        let f = Shape::place(f.shape_type, f.direction, 3, 20).unwrap();
+        let f = f.turn_180(|body| body.iter().all(|p| p.y < 22)).unwrap();
+        assert_eq!(cells(&f), vec![(3, 20), (4, 20), (5, 20), (3, 21)]);
+
+        let f = Shape::new(ShapeType::Quadrat, Direction::Top, 10);
+        let f = f.turn_180(free).unwrap();
+        assert_eq!(cells(&f), vec![(4, 0), (5, 0), (4, 1), (5, 1)]);
+    }
 
-        assert_eq!(f.body[3].x, 4);
-        assert_eq!(f.body[3].y, 3);
+    #[test]
+    fn floor_kick_test() {
+        let f = Shape::new(ShapeType::Line, Direction::Top, 10);
+        let f = Shape::place(f.shape_type, f.direction, 3, 20).unwrap();
+        assert_eq!(cells(&f), vec![(3, 21), (4, 21), (5, 21), (6, 21)]);
+
+        // The vertical line does not fit below row 21 until the kick lifts it.
+        let f = f.turn_left(|body| body.iter().all(|p| p.y < 22)).unwrap();
+        assert_eq!(f.direction(), Direction::Left);
+        assert_eq!(cells(&f), vec![(3, 18), (3, 19), (3, 20), (3, 21)]);
     }
 }
@@ -0,0 +1,317 @@
+use crossterm::style::Color;
+use std::env;
+use tetris::{board::cell_value, shapes::ShapeType};
+
+/// How many colours the terminal can show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorDepth {
+    TrueColor,
+    Ansi256,
+    Ansi16,
+}
+
+impl ColorDepth {
+    pub fn from_name(name: &str) -> Option<ColorDepth> {
+        match name {
+            "truecolor" => Some(ColorDepth::TrueColor),
+            "256" => Some(ColorDepth::Ansi256),
+            "16" => Some(ColorDepth::Ansi16),
+            _ => None,
+        }
+    }
+
+    /// Guesses the depth from `COLORTERM` and `TERM`, the way most terminals advertise it.
+    pub fn detect() -> ColorDepth {
+        let colorterm = env::var("COLORTERM").unwrap_or_default();
+        let term = env::var("TERM").unwrap_or_default();
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            ColorDepth::TrueColor
+        } else if term.contains("256color") {
+            ColorDepth::Ansi256
+        } else {
+            ColorDepth::Ansi16
+        }
+    }
+}
+
+/// The colours the game is drawn with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    /// Colours of the shapes by their cell value less one.
+    pieces: [Color; 7],
+    pub ghost: Color,
+    pub border: Color,
+    /// Rows flashing while they clear.
+    pub clear: Color,
+    pub text: Color,
+    pub value: Color,
+    /// The label of special clears such as T-spins.
+    pub action: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::from_name("guideline").unwrap()
+    }
+}
+
+impl Theme {
+    pub fn from_name(name: &str) -> Option<Theme> {
+        let rgb = |r, g, b| Color::Rgb { r, g, b };
+        let classic = Theme {
+            pieces: [
+                Color::DarkCyan,
+                Color::DarkGreen,
+                Color::DarkRed,
+                Color::DarkBlue,
+                Color::DarkMagenta,
+                Color::Red,
+                Color::DarkYellow,
+            ],
+            ghost: Color::DarkGrey,
+            border: Color::Yellow,
+            clear: Color::White,
+            text: Color::DarkGreen,
+            value: Color::DarkRed,
+            action: Color::Magenta,
+        };
+
+        match name {
+            "classic" => Some(classic),
+            // T, L, J, I, S, Z and O in purple, orange, blue, cyan, green, red and yellow.
+            "guideline" => Some(Theme {
+                pieces: [
+                    rgb(160, 0, 240),
+                    rgb(255, 165, 0),
+                    rgb(0, 0, 255),
+                    rgb(0, 255, 255),
+                    rgb(0, 255, 0),
+                    rgb(255, 0, 0),
+                    rgb(255, 255, 0),
+                ],
+                ghost: rgb(128, 128, 128),
+                ..classic
+            }),
+            "monochrome" => Some(Theme {
+                pieces: [Color::Reset; 7],
+                ghost: Color::Reset,
+                border: Color::Reset,
+                clear: Color::Reset,
+                text: Color::Reset,
+                value: Color::Reset,
+                action: Color::Reset,
+            }),
+            // The guideline colours darkened to stand out on a white background.
+            "light" => Some(Theme {
+                pieces: [
+                    rgb(128, 0, 128),
+                    rgb(210, 105, 30),
+                    rgb(0, 0, 205),
+                    rgb(0, 139, 139),
+                    rgb(0, 128, 0),
+                    rgb(178, 34, 34),
+                    rgb(184, 134, 11),
+                ],
+                ghost: rgb(170, 170, 170),
+                border: rgb(96, 96, 96),
+                clear: Color::Black,
+                text: rgb(0, 100, 0),
+                value: rgb(139, 0, 0),
+                action: rgb(128, 0, 128),
+            }),
+            _ => None,
+        }
+    }
+
+    /// The colour of a board cell, which has to hold a shape.
+    pub fn piece(&self, cell: u8) -> Color {
+        self.pieces[cell as usize - 1]
+    }
+
+    /// Changes one colour, a shape by its letter or one of ghost, border, clear, text,
+    /// value and action.
+    pub fn set(&mut self, name: &str, color: Color) -> Result<(), String> {
+        let mut letters = name.chars();
+        let shape_type = match (letters.next(), letters.next()) {
+            (Some(c), None) => ShapeType::from_letter(c),
+            _ => None,
+        };
+        let target = match (shape_type, name) {
+            (Some(shape_type), _) => &mut self.pieces[cell_value(shape_type) as usize - 1],
+            (_, "ghost") => &mut self.ghost,
+            (_, "border") => &mut self.border,
+            (_, "clear") => &mut self.clear,
+            (_, "text") => &mut self.text,
+            (_, "value") => &mut self.value,
+            (_, "action") => &mut self.action,
+            _ => return Err("unknown setting".to_string()),
+        };
+        *target = color;
+        Ok(())
+    }
+
+    /// Replaces the colours the terminal cannot show with the closest ones it can.
+    pub fn downgrade(&self, depth: ColorDepth) -> Theme {
+        let d = |color| downgrade(color, depth);
+        Theme {
+            pieces: self.pieces.map(d),
+            ghost: d(self.ghost),
+            border: d(self.border),
+            clear: d(self.clear),
+            text: d(self.text),
+            value: d(self.value),
+            action: d(self.action),
+        }
+    }
+}
+
+/// Reads a colour such as "#a000f0", "dark_cyan" or "default".
+pub fn parse_color(name: &str) -> Option<Color> {
+    if let Some(hex) = name.strip_prefix('#') {
+        let value = u32::from_str_radix(hex, 16)
+            .ok()
+            .filter(|_| hex.len() == 6)?;
+        let [_, r, g, b] = value.to_be_bytes();
+        return Some(Color::Rgb { r, g, b });
+    }
+
+    match name {
+        "default" => Some(Color::Reset),
+        name => Color::try_from(name).ok(),
+    }
+}
+
+/// The sixteen standard colours as xterm shows them.
+const ANSI16: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::DarkRed, (205, 0, 0)),
+    (Color::DarkGreen, (0, 205, 0)),
+    (Color::DarkYellow, (205, 205, 0)),
+    (Color::DarkBlue, (0, 0, 238)),
+    (Color::DarkMagenta, (205, 0, 205)),
+    (Color::DarkCyan, (0, 205, 205)),
+    (Color::Grey, (229, 229, 229)),
+    (Color::DarkGrey, (127, 127, 127)),
+    (Color::Red, (255, 0, 0)),
+    (Color::Green, (0, 255, 0)),
+    (Color::Yellow, (255, 255, 0)),
+    (Color::Blue, (92, 92, 255)),
+    (Color::Magenta, (255, 0, 255)),
+    (Color::Cyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// Channel values of the 6x6x6 colour cube in the 256 colour palette.
+const CUBE: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn downgrade(color: Color, depth: ColorDepth) -> Color {
+    let rgb = match (color, depth) {
+        (_, ColorDepth::TrueColor) => return color,
+        (Color::Rgb { r, g, b }, _) => (r, g, b),
+        (Color::AnsiValue(value), ColorDepth::Ansi16) => ansi256_rgb(value),
+        _ => return color,
+    };
+
+    match depth {
+        ColorDepth::Ansi256 => Color::AnsiValue(nearest_ansi256(rgb)),
+        _ => {
+            ANSI16
+                .iter()
+                .min_by_key(|(_, c)| distance(*c, rgb))
+                .unwrap()
+                .0
+        }
+    }
+}
+
+fn ansi256_rgb(value: u8) -> (u8, u8, u8) {
+    match value {
+        0..=15 => ANSI16[value as usize].1,
+        16..=231 => {
+            let i = value as usize - 16;
+            (CUBE[i / 36], CUBE[i / 6 % 6], CUBE[i % 6])
+        }
+        _ => {
+            let grey = 8 + (value - 232) * 10;
+            (grey, grey, grey)
+        }
+    }
+}
+
+/// The closest colour of the cube or the grey ramp, leaving out the first sixteen which
+/// terminals are free to change.
+fn nearest_ansi256(rgb: (u8, u8, u8)) -> u8 {
+    (16..=255)
+        .min_by_key(|value| distance(ansi256_rgb(*value), rgb))
+        .unwrap()
+}
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let d = |x: u8, y: u8| (x as i32 - y as i32).pow(2) as u32;
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_color_test() {
+        assert_eq!(
+            parse_color("#a000F0"),
+            Some(Color::Rgb {
+                r: 160,
+                g: 0,
+                b: 240
+            })
+        );
+        assert_eq!(parse_color("dark_cyan"), Some(Color::DarkCyan));
+        assert_eq!(parse_color("default"), Some(Color::Reset));
+        assert_eq!(parse_color("#a000f"), None);
+        assert_eq!(parse_color("#a000fg"), None);
+        assert_eq!(parse_color("teal"), None);
+    }
+
+    #[test]
+    fn downgrade_test() {
+        let guideline = Theme::default();
+        assert_eq!(guideline.downgrade(ColorDepth::TrueColor), guideline);
+
+        let ansi256 = guideline.downgrade(ColorDepth::Ansi256);
+        assert_eq!(ansi256.piece(4), Color::AnsiValue(51));
+        assert_eq!(ansi256.piece(2), Color::AnsiValue(214));
+        assert_eq!(ansi256.ghost, Color::AnsiValue(244));
+        assert_eq!(ansi256.border, Color::Yellow);
+
+        let ansi16 = guideline.downgrade(ColorDepth::Ansi16);
+        let pieces = (1..=7).map(|cell| ansi16.piece(cell)).collect::<Vec<_>>();
+        assert_eq!(
+            pieces,
+            vec![
+                Color::DarkMagenta,
+                Color::DarkYellow,
+                Color::DarkBlue,
+                Color::Cyan,
+                Color::Green,
+                Color::Red,
+                Color::Yellow,
+            ]
+        );
+        assert_eq!(ansi16.ghost, Color::DarkGrey);
+        assert_eq!(
+            downgrade(Color::AnsiValue(51), ColorDepth::Ansi16),
+            Color::Cyan
+        );
+    }
+
+    #[test]
+    fn set_test() {
+        let mut theme = Theme::from_name("classic").unwrap();
+        theme.set("I", Color::Cyan).unwrap();
+        theme.set("ghost", Color::Grey).unwrap();
+        assert_eq!(theme.piece(cell_value(ShapeType::Line)), Color::Cyan);
+        assert_eq!(theme.ghost, Color::Grey);
+        assert!(theme.set("X", Color::Cyan).is_err());
+        assert!(theme.set("background", Color::Cyan).is_err());
+    }
+}